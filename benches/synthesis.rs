@@ -0,0 +1,63 @@
+// cargo bench --bench synthesis
+use std::time::Instant;
+
+use morsecord::cw_audio::{CWAudioPCM, Effects, Tone, Waveform};
+
+const SRATE: usize = 48000;
+
+fn bench(name: &str, mut pcm: CWAudioPCM) {
+    let secs = pcm.len_samples() as f64 / SRATE as f64;
+    let mut buf = vec![0.0; 960]; // 20ms, as songbird reads
+    let start = Instant::now();
+    let mut n = 0;
+    loop {
+        let r = pcm.read_samples(&mut buf);
+        if r == 0 {
+            break;
+        }
+        n += r;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "{:<12} {:>8.1} Msamples/s, {:>6.0}x realtime",
+        name,
+        n as f64 / elapsed / 1e6,
+        secs / elapsed
+    );
+}
+
+// per-sample sin(), for comparison
+fn naive(len: usize) {
+    let omega = 2.0 * std::f32::consts::PI * 600.0 / SRATE as f32;
+    let start = Instant::now();
+    let mut acc = 0.0;
+    for i in 0..len {
+        acc += (omega * i as f32).sin();
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    std::hint::black_box(acc);
+    println!(
+        "{:<12} {:>8.1} Msamples/s",
+        "naive sin",
+        len as f64 / elapsed / 1e6
+    );
+}
+
+fn main() {
+    let text = "CQ CQ DE JA1ABC JA1ABC PSE K ".repeat(20);
+    let pcm = || CWAudioPCM::new(text.clone(), 25.0, 600.0, SRATE);
+
+    naive(pcm().len_samples());
+    bench("sine", pcm());
+    for w in [Waveform::SoftSquare, Waveform::Buzzy] {
+        let tone = Tone {
+            waveform: w,
+            ..Tone::default()
+        };
+        bench(w.name(), pcm().with_tone(&tone));
+    }
+    bench(
+        "effects",
+        pcm().with_effects(&"all".parse::<Effects>().unwrap()),
+    );
+}
@@ -0,0 +1,299 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use morsecord::cw_audio::{
+    AudioFile, CWAudioPCM, Effects, FileFormat, NoiseColor, NoiseConfig, SampleFormat, Tone,
+};
+use morsecord::morse::{EncodeOptions, Fist, Timeline, Timing};
+
+const USAGE: &str = "\
+usage: cwgen [options] [--] [text...]
+
+text is taken from the arguments, the --input files, or stdin
+arguments after -- are text even if they start with -
+
+  -o, --output PATH      output file, - for stdout (default: out.wav)
+  -i, --input FILE       read text from FILE, - for stdin; can be repeated
+      --batch DIR        convert every .txt file in DIR into the --output directory
+  -w, --wpm N            character speed (default: 20)
+  -e, --effective N      overall speed, with longer gaps (Farnsworth)
+      --weight W         mark to space weight, 0.3 ~ 0.7 (default: 0.5)
+      --dash-ratio R     dash length in dots, 2 ~ 5 (default: 3)
+      --reference R      word defining wpm, paris or codex (default: paris)
+      --char-space DOTS  extra gap between characters (default: 0)
+      --word-space DOTS  extra gap between words (default: 0)
+  -f, --freq HZ          tone frequency (default: 600)
+  -r, --rate HZ          sample rate (default: 48000)
+      --format F         wav, raw or au (default: from the extension, or wav)
+  -b, --bits B           8, 16, 24 or float (default: 16)
+      --rise-ms MS       rise and fall time of each mark (default: 10)
+      --envelope E       cosine, blackman-harris, linear or hard
+      --waveform W       sine, soft-square or buzzy
+      --fist F           machine, straight, heavy, light, bug or sloppy
+      --effects E        clean, qsb, flutter, chirp or all
+      --snr DB           add band noise at this signal to noise ratio
+      --noise-color C    white or pink (default: pink)
+      --crashes N        static crashes per second (default: 0.2)
+      --bandwidth HZ     receiver filter width, 0 for none (default: 500)
+      --table T          code table looked up first
+      --no-wabun-markers do not send ホレ / ラタ between kana and letters
+      --volume V         0.0 ~ 1.0 (default: 1.0)
+      --seed N           random seed for the fist, effects and noise
+  -h, --help             show this message
+";
+
+struct Options {
+    timing: Timing,
+    freq: f32,
+    tone: Tone,
+    fist: Fist,
+    effects: Effects,
+    noise: Option<NoiseConfig>,
+    encode: EncodeOptions,
+    volume: f32,
+    file: AudioFile,
+    format: Option<FileFormat>, // None to guess from the output path
+
+    text: Vec<String>,
+    inputs: Vec<String>,
+    output: Option<String>,
+    batch: Option<PathBuf>,
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> anyhow::Result<Options> {
+    let mut o = Options {
+        timing: Timing::new(20.0),
+        freq: 600.0,
+        tone: Tone::default(),
+        fist: Fist::default(),
+        effects: Effects::default(),
+        noise: None,
+        encode: EncodeOptions::default(),
+        volume: 1.0,
+        file: AudioFile::new(48000),
+        format: None,
+
+        text: Vec::new(),
+        inputs: Vec::new(),
+        output: None,
+        batch: None,
+    };
+    let mut noise_color = None;
+    let mut crashes = None;
+    let mut bandwidth = None;
+    let mut seed = None;
+
+    let mut args = args;
+    while let Some(a) = args.next() {
+        if a == "-h" || a == "--help" {
+            print!("{USAGE}");
+            std::process::exit(0);
+        }
+        if !a.starts_with('-') {
+            o.text.push(a);
+            continue;
+        }
+        if a == "--" {
+            o.text.extend(args.by_ref());
+            break;
+        }
+        if a == "--no-wabun-markers" {
+            o.encode.wabun_markers = false;
+            continue;
+        }
+
+        let v = args
+            .next()
+            .with_context(|| format!("missing value for {a}"))?;
+        let invalid = || format!("invalid value for {a}: {v}");
+        match a.as_str() {
+            "-o" | "--output" => o.output = Some(v),
+            "-i" | "--input" => o.inputs.push(v),
+            "--batch" => o.batch = Some(v.into()),
+            "-w" | "--wpm" => o.timing.char_wpm = v.parse().with_context(invalid)?,
+            "-e" | "--effective" => o.timing.effective_wpm = Some(v.parse().with_context(invalid)?),
+            "--weight" => o.timing.weight = v.parse().with_context(invalid)?,
+            "--dash-ratio" => o.timing.dash_ratio = v.parse().with_context(invalid)?,
+            "--reference" => o.timing.reference = v.parse()?,
+            "--char-space" => o.timing.extra_char_space = v.parse().with_context(invalid)?,
+            "--word-space" => o.timing.extra_word_space = v.parse().with_context(invalid)?,
+            "-f" | "--freq" => o.freq = v.parse().with_context(invalid)?,
+            "-r" | "--rate" => o.file.srate = v.parse().with_context(invalid)?,
+            "--format" => o.format = Some(v.parse()?),
+            "-b" | "--bits" => o.file.sample = v.parse::<SampleFormat>()?,
+            "--rise-ms" => o.tone.rise_ms = v.parse().with_context(invalid)?,
+            "--envelope" => o.tone.envelope = v.parse()?,
+            "--waveform" => o.tone.waveform = v.parse()?,
+            "--fist" => o.fist = v.parse()?,
+            "--effects" => o.effects = v.parse()?,
+            "--snr" => o.noise = Some(NoiseConfig::new(v.parse().with_context(invalid)?)),
+            "--noise-color" => noise_color = Some(v.parse::<NoiseColor>()?),
+            "--crashes" => crashes = Some(v.parse().with_context(invalid)?),
+            "--bandwidth" => bandwidth = Some(v.parse::<f32>().with_context(invalid)?),
+            "--table" => o.encode.table = v.parse()?,
+            "--volume" => o.volume = v.parse().with_context(invalid)?,
+            "--seed" => seed = Some(v.parse::<u64>().with_context(invalid)?),
+            _ => anyhow::bail!("unknown option: {a}\n\n{USAGE}"),
+        }
+    }
+
+    anyhow::ensure!(o.timing.char_wpm > 0.0, "wpm must be positive");
+    anyhow::ensure!(
+        o.timing.effective_wpm.is_none_or(|e| e > 0.0),
+        "effective speed must be positive"
+    );
+    anyhow::ensure!(
+        (0.3..=0.7).contains(&o.timing.weight),
+        "weight must be between 0.3 and 0.7"
+    );
+    anyhow::ensure!(
+        (2.0..=5.0).contains(&o.timing.dash_ratio),
+        "dash ratio must be between 2 and 5"
+    );
+    anyhow::ensure!(
+        o.timing.extra_char_space >= 0.0 && o.timing.extra_word_space >= 0.0,
+        "extra spacing must not be negative"
+    );
+    anyhow::ensure!(
+        o.text.is_empty() || o.inputs.is_empty(),
+        "text arguments and --input are exclusive"
+    );
+    anyhow::ensure!(o.file.srate > 0, "sample rate must be positive");
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&o.volume),
+        "volume must be between 0.0 and 1.0"
+    );
+    anyhow::ensure!(
+        o.freq > 0.0 && o.freq < o.file.srate as f32 / 2.0,
+        "frequency must be below half the sample rate"
+    );
+
+    // each one gets its own stream of the same seed
+    o.fist.seed = seed;
+    o.effects.seed = seed;
+    match &mut o.noise {
+        None => {
+            let given = [
+                ("--noise-color", noise_color.is_some()),
+                ("--crashes", crashes.is_some()),
+                ("--bandwidth", bandwidth.is_some()),
+            ];
+            if let Some((a, _)) = given.iter().find(|(_, g)| *g) {
+                anyhow::bail!("{a} needs --snr");
+            }
+        }
+        Some(n) => {
+            if let Some(c) = noise_color {
+                n.color = c;
+            }
+            if let Some(c) = crashes {
+                n.crashes = c;
+            }
+            if let Some(bw) = bandwidth {
+                n.bandwidth = Some(bw).filter(|&bw| bw > 0.0);
+            }
+            n.seed = seed;
+        }
+    }
+    Ok(o)
+}
+
+fn render(text: &str, o: &Options) -> Vec<f32> {
+    let codes = morsecord::morse::get_morse_str_with(text.to_string(), &o.encode);
+    let timeline = Timeline::with_fist(&codes, &o.timing, &o.fist);
+    let mut pcm = CWAudioPCM::from_timeline(&timeline, o.freq, o.file.srate)
+        .with_tone(&o.tone)
+        .with_volume(o.volume)
+        .with_effects(&o.effects);
+    if let Some(n) = &o.noise {
+        pcm = pcm.with_noise(n);
+    }
+    pcm.render()
+}
+
+fn read_text(path: &str) -> anyhow::Result<String> {
+    if path == "-" {
+        let mut s = String::new();
+        std::io::stdin()
+            .read_to_string(&mut s)
+            .context("failed to read stdin")?;
+        Ok(s)
+    } else {
+        std::fs::read_to_string(path).with_context(|| format!("failed to read {path}"))
+    }
+}
+
+fn write_file(path: &str, file: &AudioFile, samples: &[f32]) -> anyhow::Result<()> {
+    if path == "-" {
+        return file
+            .write(&mut std::io::stdout().lock(), samples)
+            .context("failed to write stdout");
+    }
+    let f = std::fs::File::create(path).with_context(|| format!("failed to create {path}"))?;
+    file.write(&mut std::io::BufWriter::new(f), samples)
+        .with_context(|| format!("failed to write {path}"))
+}
+
+// converts each .txt in dir into out_dir
+fn batch(dir: &Path, out_dir: &Path, o: &Options) -> anyhow::Result<()> {
+    let format = o.format.unwrap_or_default();
+    let file = AudioFile {
+        format,
+        ..o.file.clone()
+    };
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("failed to create {}", out_dir.display()))?;
+
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "txt"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    for p in paths {
+        let text = std::fs::read_to_string(&p)
+            .with_context(|| format!("failed to read {}", p.display()))?;
+        // appended to the whole stem, which may have dots of its own
+        let mut name = p.file_stem().context("no file name")?.to_os_string();
+        name.push(".");
+        name.push(format.name());
+        let out = out_dir.join(name);
+        write_file(&out.to_string_lossy(), &file, &render(&text, o))?;
+        eprintln!("{} -> {}", p.display(), out.display());
+    }
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let o = parse_args(std::env::args().skip(1))?;
+
+    if let Some(dir) = &o.batch {
+        let out = o.output.as_deref().unwrap_or(".");
+        return batch(dir, Path::new(out), &o);
+    }
+
+    let text = if !o.inputs.is_empty() {
+        o.inputs
+            .iter()
+            .map(|p| read_text(p))
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .join(" ")
+    } else if !o.text.is_empty() {
+        o.text.join(" ")
+    } else {
+        read_text("-")?
+    };
+
+    let output = o.output.as_deref().unwrap_or("out.wav");
+    let file = AudioFile {
+        format: o
+            .format
+            .or_else(|| FileFormat::from_path(output.as_ref()))
+            .unwrap_or_default(),
+        ..o.file.clone()
+    };
+    write_file(output, &file, &render(&text, &o))
+}
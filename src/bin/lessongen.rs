@@ -0,0 +1,316 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use rand::Rng;
+
+use morsecord::cw_audio::{AudioFile, CWAudioPCM, Effects, FileFormat, NoiseConfig, SampleFormat};
+use morsecord::modes::lesson::{get_lesson_gen, LessonOptions};
+use morsecord::morse::{EncodeOptions, Fist, Timeline, Timing};
+
+const USAGE: &str = "\
+usage: lessongen [options] [probset]
+
+renders lesson questions into one audio file, with an answer key
+probset is the same as /cw-start-lesson, e.g. call_ja, nr_acag or file:foo.txt (default: call_ja)
+
+  -n, --count N          number of questions (default: 20)
+  -o, --output PATH      output audio file (default: lesson.wav)
+  -k, --key PATH         answer key, .json for JSON, otherwise text (default: output with .key.txt)
+  -p, --pause SECS       silence after each question (default: 5)
+      --min-speed N      (default: 20)
+      --max-speed N      (default: same as min)
+      --effective N      overall speed, with longer gaps (Farnsworth)
+      --min-freq HZ      (default: 600)
+      --max-freq HZ      (default: same as min)
+      --min-snr DB       add band noise; no noise if not given
+      --max-snr DB       (default: same as min)
+      --noise-color C    white or pink (default: pink)
+      --crashes N        static crashes per second (default: 0.2)
+      --bandwidth HZ     receiver filter width, 0 for none (default: 500)
+      --rise-ms MS       rise and fall time of each mark (default: 10)
+      --envelope E       cosine, blackman-harris, linear or hard
+      --waveform W       sine, soft-square or buzzy
+      --fist F           machine, straight, heavy, light, bug or sloppy
+      --effects E        clean, qsb, flutter, chirp or all
+      --table T          code table looked up first
+      --no-wabun-markers do not send ホレ / ラタ between kana and letters
+      --volume V         0.0 ~ 1.0 (default: 1.0)
+      --seed N           random seed for the questions, speeds, fist, effects and noise
+  -r, --rate HZ          sample rate (default: 48000)
+  -b, --bits B           8, 16, 24 or float (default: 16)
+  -h, --help             show this message
+";
+
+struct Options {
+    probset: String,
+    count: usize,
+    output: PathBuf,
+    key: PathBuf,
+    pause: f32,
+    speed: (f32, Option<f32>),
+    freq: (f32, Option<f32>),
+    lesson: LessonOptions,
+    seed: Option<u64>,
+    file: AudioFile,
+}
+
+#[derive(serde::Serialize)]
+struct Question {
+    index: usize,
+    start: f64, // in seconds
+    end: f64,
+    wpm: f32,
+    freq: f32,
+    snr: Option<f32>,
+    answer: String,
+}
+
+#[derive(serde::Serialize)]
+struct AnswerKey<'a> {
+    probset: &'a str,
+    audio: &'a Path,
+    questions: &'a [Question],
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> anyhow::Result<Options> {
+    let mut o = Options {
+        probset: "call_ja".to_string(),
+        count: 20,
+        output: "lesson.wav".into(),
+        key: PathBuf::new(),
+        pause: 5.0,
+        speed: (20.0, None),
+        freq: (600.0, None),
+        lesson: LessonOptions::default(),
+        seed: None,
+        file: AudioFile::new(48000),
+    };
+    let mut key = None;
+    let mut min_snr = None;
+    let mut max_snr = None;
+
+    let mut args = args;
+    while let Some(a) = args.next() {
+        if a == "-h" || a == "--help" {
+            print!("{USAGE}");
+            std::process::exit(0);
+        }
+        if !a.starts_with('-') {
+            o.probset = a;
+            continue;
+        }
+        if a == "--no-wabun-markers" {
+            o.lesson.wabun_markers = false;
+            continue;
+        }
+
+        let v = args
+            .next()
+            .with_context(|| format!("missing value for {a}"))?;
+        let invalid = || format!("invalid value for {a}: {v}");
+        match a.as_str() {
+            "-n" | "--count" => o.count = v.parse().with_context(invalid)?,
+            "-o" | "--output" => o.output = v.into(),
+            "-k" | "--key" => key = Some(v.into()),
+            "-p" | "--pause" => o.pause = v.parse().with_context(invalid)?,
+            "--min-speed" => o.speed.0 = v.parse().with_context(invalid)?,
+            "--max-speed" => o.speed.1 = Some(v.parse().with_context(invalid)?),
+            "--effective" => o.lesson.effective_speed = Some(v.parse().with_context(invalid)?),
+            "--min-freq" => o.freq.0 = v.parse().with_context(invalid)?,
+            "--max-freq" => o.freq.1 = Some(v.parse().with_context(invalid)?),
+            "--min-snr" => min_snr = Some(v.parse().with_context(invalid)?),
+            "--max-snr" => max_snr = Some(v.parse().with_context(invalid)?),
+            "--noise-color" => o.lesson.noise.color = v.parse()?,
+            "--crashes" => o.lesson.noise.crashes = v.parse().with_context(invalid)?,
+            "--bandwidth" => {
+                o.lesson.noise.bandwidth =
+                    Some(v.parse().with_context(invalid)?).filter(|&bw| bw > 0.0)
+            }
+            "--rise-ms" => o.lesson.tone.rise_ms = v.parse().with_context(invalid)?,
+            "--envelope" => o.lesson.tone.envelope = v.parse()?,
+            "--waveform" => o.lesson.tone.waveform = v.parse()?,
+            "--fist" => o.lesson.fist = v.parse()?,
+            "--effects" => o.lesson.effects = v.parse()?,
+            "--table" => o.lesson.table = v.parse()?,
+            "--volume" => o.lesson.volume = v.parse().with_context(invalid)?,
+            "--seed" => o.seed = Some(v.parse().with_context(invalid)?),
+            "-r" | "--rate" => o.file.srate = v.parse().with_context(invalid)?,
+            "-b" | "--bits" => o.file.sample = v.parse::<SampleFormat>()?,
+            _ => anyhow::bail!("unknown option: {a}\n\n{USAGE}"),
+        }
+    }
+
+    o.lesson.snr_range = match (min_snr, max_snr) {
+        (None, None) => None,
+        (None, Some(_)) => anyhow::bail!("--max-snr needs --min-snr"),
+        (Some(min), max) => {
+            let max = max.unwrap_or(min);
+            anyhow::ensure!(min <= max, "min is larger than max: {min} > {max}");
+            Some(min..=max)
+        }
+    };
+    // the key is written after the audio and would overwrite it
+    o.key = key.unwrap_or_else(|| o.output.with_extension("key.txt"));
+    anyhow::ensure!(
+        o.key != o.output,
+        "answer key and output are the same file: {}",
+        o.key.display()
+    );
+    anyhow::ensure!(o.speed.0 > 0.0, "speed must be positive");
+    anyhow::ensure!(
+        o.lesson.effective_speed.is_none_or(|e| e > 0.0),
+        "effective speed must be positive"
+    );
+    anyhow::ensure!(o.freq.0 > 0.0, "frequency must be positive");
+    for (min, max) in [o.speed, o.freq] {
+        let max = max.unwrap_or(min);
+        anyhow::ensure!(min <= max, "min is larger than max: {min} > {max}");
+    }
+    anyhow::ensure!(o.pause >= 0.0, "pause must not be negative");
+    anyhow::ensure!(o.file.srate > 0, "sample rate must be positive");
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&o.lesson.volume),
+        "volume must be between 0.0 and 1.0"
+    );
+    anyhow::ensure!(
+        o.freq.1.unwrap_or(o.freq.0) < o.file.srate as f32 / 2.0,
+        "frequency must be below half the sample rate"
+    );
+    Ok(o)
+}
+
+// picks from min ..= max, like the live lesson
+fn pick(rng: &mut impl Rng, (min, max): (f32, Option<f32>)) -> f32 {
+    rng.gen_range(min..=max.unwrap_or(min))
+}
+
+fn format_time(secs: f64) -> String {
+    let ms = (secs * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+fn write_key(path: &Path, o: &Options, questions: &[Question]) -> anyhow::Result<()> {
+    let f = std::fs::File::create(path)
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    let mut w = std::io::BufWriter::new(f);
+
+    if path.extension().is_some_and(|e| e == "json") {
+        let key = AnswerKey {
+            probset: &o.probset,
+            audio: &o.output,
+            questions,
+        };
+        serde_json::to_writer_pretty(&mut w, &key)?;
+        writeln!(w)?;
+    } else {
+        writeln!(w, "# {} ({})", o.probset, o.output.display())?;
+        for q in questions {
+            write!(
+                w,
+                "{:>3}  {}  {:>2.0} wpm  {:>4.0} Hz",
+                q.index,
+                format_time(q.start),
+                q.wpm,
+                q.freq
+            )?;
+            if let Some(snr) = q.snr {
+                write!(w, "  {:>3.0} dB", snr)?;
+            }
+            writeln!(w, "  {}", q.answer)?;
+        }
+    }
+    w.flush()
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn main() -> anyhow::Result<()> {
+    let o = parse_args(std::env::args().skip(1))?;
+    let gen = get_lesson_gen(&o.probset, o.seed)?;
+    let mut rng = morsecord::util::rng(o.seed);
+
+    let lesson = &o.lesson;
+    let srate = o.file.srate;
+    let encode = EncodeOptions {
+        wabun_markers: lesson.wabun_markers,
+        table: lesson.table,
+    };
+    let pause = vec![0.0; (o.pause * srate as f32) as usize];
+
+    let mut samples = Vec::new();
+    let mut questions = Vec::new();
+    for (i, ans) in gen.take(o.count).enumerate() {
+        let answer = ans.into_str().to_string();
+        let wpm = pick(&mut rng, o.speed);
+        let freq = pick(&mut rng, o.freq);
+        let snr = lesson.snr_range.clone().map(|r| rng.gen_range(r));
+        // each question gets its own seeds, drawn from the main one
+        let mut seed = || o.seed.map(|_| rng.gen::<u64>());
+
+        let codes = morsecord::morse::get_morse_str_with(answer.clone(), &encode);
+        let timing = Timing {
+            effective_wpm: lesson.effective_speed,
+            ..Timing::new(wpm)
+        };
+        let fist = Fist {
+            seed: seed(),
+            ..lesson.fist.clone()
+        };
+        let effects = Effects {
+            seed: seed(),
+            ..lesson.effects.clone()
+        };
+        let timeline = Timeline::with_fist(&codes, &timing, &fist);
+        let mut pcm = CWAudioPCM::from_timeline(&timeline, freq, srate)
+            .with_tone(&lesson.tone)
+            .with_volume(lesson.volume)
+            .with_effects(&effects);
+        if let Some(snr) = snr {
+            pcm = pcm.with_noise(&NoiseConfig {
+                snr_db: snr,
+                seed: seed(),
+                ..lesson.noise.clone()
+            });
+        }
+
+        let start = samples.len() as f64 / srate as f64;
+        samples.extend(pcm.render());
+        questions.push(Question {
+            index: i + 1,
+            start,
+            end: samples.len() as f64 / srate as f64,
+            wpm,
+            freq,
+            snr,
+            answer,
+        });
+        samples.extend_from_slice(&pause);
+    }
+    anyhow::ensure!(!questions.is_empty(), "no questions");
+
+    let file = AudioFile {
+        format: FileFormat::from_path(&o.output).unwrap_or_default(),
+        ..o.file.clone()
+    };
+    let f = std::fs::File::create(&o.output)
+        .with_context(|| format!("failed to create {}", o.output.display()))?;
+    file.write(&mut std::io::BufWriter::new(f), &samples)
+        .with_context(|| format!("failed to write {}", o.output.display()))?;
+
+    write_key(&o.key, &o, &questions)?;
+
+    eprintln!(
+        "{} questions, {} -> {}, {}",
+        questions.len(),
+        format_time(samples.len() as f64 / srate as f64),
+        o.output.display(),
+        o.key.display()
+    );
+    Ok(())
+}
@@ -5,6 +5,8 @@ use serenity::model::prelude::command::CommandOptionType;
 use serenity::prelude::Context;
 
 use crate::bot::commands::get_value_f64;
+use crate::cw_audio::{Envelope, Waveform};
+use crate::morse::{CodeTable, Fist, ReferenceWord};
 
 impl crate::bot::Bot {
     pub async fn run_command_speed(
@@ -20,9 +22,35 @@ impl crate::bot::Bot {
             .map(|option| get_value_f64(&option.value))
             .context("no argument")??;
 
-        sqlx::query("insert into cw_speed (id, speed) values (?, ?) on conflict (id) do update set speed = excluded.speed")
+        let mut effective = None;
+        let mut weight = 0.5;
+        let mut dash_ratio = 3.0;
+        let mut reference = ReferenceWord::default();
+        for x in &command.data.options {
+            match x.name.as_str() {
+                "effective" => effective = Some(get_value_f64(&x.value)?),
+                "weight" => weight = get_value_f64(&x.value)?,
+                "dash_ratio" => dash_ratio = get_value_f64(&x.value)?,
+                "reference" => {
+                    reference = x
+                        .value
+                        .as_ref()
+                        .and_then(|v| v.as_str())
+                        .context("value is not string")?
+                        .parse()?
+                }
+                _ => (),
+            }
+        }
+
+        // omitted options go back to standard timing
+        sqlx::query("insert into cw_speed (id, speed, effective_speed, weight, dash_ratio, reference_word) values (?, ?, ?, ?, ?, ?) on conflict (id) do update set speed = excluded.speed, effective_speed = excluded.effective_speed, weight = excluded.weight, dash_ratio = excluded.dash_ratio, reference_word = excluded.reference_word")
             .bind(command.user.id.to_string())
             .bind(new_speed)
+            .bind(effective)
+            .bind(weight)
+            .bind(dash_ratio)
+            .bind(reference.name())
             .execute(&self.db)
             .await
             .context("internal error")?;
@@ -53,6 +81,192 @@ impl crate::bot::Bot {
         Ok("ok!".to_string())
     }
 
+    pub async fn run_command_table(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let table = command
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "table")
+            .and_then(|option| option.value.as_ref())
+            .and_then(|v| v.as_str())
+            .context("no argument")?
+            .parse::<CodeTable>()?;
+        // omitted goes back to on
+        let wabun_markers = command
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "wabun_markers")
+            .and_then(|option| option.value.as_ref())
+            .map(|v| v.as_bool().context("value is not bool"))
+            .transpose()?
+            .unwrap_or(true);
+
+        sqlx::query("insert into cw_speed (id, code_table, wabun_markers) values (?, ?, ?) on conflict (id) do update set code_table = excluded.code_table, wabun_markers = excluded.wabun_markers")
+            .bind(command.user.id.to_string())
+            .bind(table.name())
+            .bind(wabun_markers)
+            .execute(&self.db)
+            .await
+            .context("internal error")?;
+
+        Ok("ok!".to_string())
+    }
+
+    pub async fn run_command_fist(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let fist = command
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "fist")
+            .and_then(|option| option.value.as_ref())
+            .and_then(|v| v.as_str())
+            .context("no argument")?
+            .to_ascii_lowercase();
+        fist.parse::<Fist>()?;
+
+        sqlx::query("insert into cw_speed (id, fist) values (?, ?) on conflict (id) do update set fist = excluded.fist")
+            .bind(command.user.id.to_string())
+            .bind(fist)
+            .execute(&self.db)
+            .await
+            .context("internal error")?;
+
+        Ok("ok!".to_string())
+    }
+
+    pub async fn run_command_tone(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let mut updated = false;
+        for option in &command.data.options {
+            let v = option.value.as_ref().context("value empty")?;
+            let vs = v.as_str().context("value is not string");
+
+            let col = match option.name.as_str() {
+                c @ ("rise_ms" | "envelope" | "waveform") => c,
+                _ => continue,
+            };
+            // column names are fixed above, only values are from the user
+            let sql = format!("insert into cw_speed (id, {col}) values (?, ?) on conflict (id) do update set {col} = excluded.{col}");
+            let query = sqlx::query(&sql).bind(command.user.id.to_string());
+            let query = match col {
+                "rise_ms" => query.bind(get_value_f64(&option.value)?),
+                "envelope" => query.bind(vs?.parse::<Envelope>()?.name()),
+                _ => query.bind(vs?.parse::<Waveform>()?.name()),
+            };
+            query.execute(&self.db).await.context("internal error")?;
+            updated = true;
+        }
+        anyhow::ensure!(updated, "no argument");
+
+        Ok("ok!".to_string())
+    }
+
+    pub async fn run_command_volume(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let volume = command
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "volume")
+            .map(|option| get_value_f64(&option.value))
+            .context("no argument")??;
+
+        sqlx::query("insert into cw_speed (id, volume) values (?, ?) on conflict (id) do update set volume = excluded.volume")
+            .bind(command.user.id.to_string())
+            .bind(volume)
+            .execute(&self.db)
+            .await
+            .context("internal error")?;
+
+        Ok("ok!".to_string())
+    }
+
+    pub async fn run_command_pan(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        // no argument to go back to mono
+        let pan = command
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "pan")
+            .map(|option| get_value_f64(&option.value))
+            .transpose()?;
+
+        sqlx::query("insert into cw_speed (id, pan) values (?, ?) on conflict (id) do update set pan = excluded.pan")
+            .bind(command.user.id.to_string())
+            .bind(pan)
+            .execute(&self.db)
+            .await
+            .context("internal error")?;
+
+        Ok("ok!".to_string())
+    }
+
+    pub async fn run_command_guild_volume(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let volume = command
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "volume")
+            .map(|option| get_value_f64(&option.value))
+            .context("no argument")??;
+
+        sqlx::query("insert into guild_config (id, volume) values (?, ?) on conflict (id) do update set volume = excluded.volume")
+            .bind(command.guild_id.context("not in guild")?.to_string())
+            .bind(volume)
+            .execute(&self.db)
+            .await
+            .context("internal error")?;
+
+        Ok("ok!".to_string())
+    }
+
+    pub async fn run_command_guild_queue(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let enable = command
+            .data
+            .options
+            .iter()
+            .find(|option| option.name == "enable")
+            .and_then(|option| option.value.as_ref())
+            .and_then(|v| v.as_bool())
+            .context("no argument")?;
+
+        sqlx::query("insert into guild_config (id, queue) values (?, ?) on conflict (id) do update set queue = excluded.queue")
+            .bind(command.guild_id.context("not in guild")?.to_string())
+            .bind(enable)
+            .execute(&self.db)
+            .await
+            .context("internal error")?;
+
+        Ok("ok!".to_string())
+    }
+
     pub async fn register_commands_cw(&self, ctx: &Context) -> anyhow::Result<()> {
         Command::create_global_application_command(&ctx.http, |command| {
             command
@@ -66,6 +280,43 @@ impl crate::bot::Bot {
                         .min_number_value(5.0)
                         .required(true)
                 })
+                .create_option(|option| {
+                    option
+                        .name("effective")
+                        .description("effective speed(wpm), gaps are stretched (Farnsworth)")
+                        .kind(CommandOptionType::Number)
+                        .min_number_value(1.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("weight")
+                        .description("mark to space weight (0.5 = standard)")
+                        .kind(CommandOptionType::Number)
+                        .min_number_value(0.3)
+                        .max_number_value(0.7)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("dash_ratio")
+                        .description("dash length in dots (3 = standard)")
+                        .kind(CommandOptionType::Number)
+                        .min_number_value(2.0)
+                        .max_number_value(5.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("reference")
+                        .description("word defining wpm")
+                        .kind(CommandOptionType::String)
+                        .required(false);
+                    for r in ReferenceWord::ALL {
+                        option.add_string_choice(r.name(), r.name());
+                    }
+                    option
+                })
         })
         .await
         .context("command cw-speed registration failed")?;
@@ -86,6 +337,160 @@ impl crate::bot::Bot {
         .await
         .context("command cw-freq registration failed")?;
 
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-table")
+                .description("set code table used for letters")
+                .create_option(|option| {
+                    option
+                        .name("table")
+                        .description("code table")
+                        .kind(CommandOptionType::String)
+                        .required(true);
+                    for t in CodeTable::ALL {
+                        option.add_string_choice(t.name(), t.name());
+                    }
+                    option
+                })
+                .create_option(|option| {
+                    option
+                        .name("wabun_markers")
+                        .description("send ホレ / ラタ when switching between kana and letters (default: true)")
+                        .kind(CommandOptionType::Boolean)
+                        .required(false)
+                })
+        })
+        .await
+        .context("command cw-table registration failed")?;
+
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-fist")
+                .description("set sender profile (timing like a human operator)")
+                .create_option(|option| {
+                    option
+                        .name("fist")
+                        .description("sender profile")
+                        .kind(CommandOptionType::String)
+                        .required(true);
+                    for p in Fist::PRESETS {
+                        option.add_string_choice(p, p);
+                    }
+                    option
+                })
+        })
+        .await
+        .context("command cw-fist registration failed")?;
+
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-tone")
+                .description("set keying envelope and tone")
+                .create_option(|option| {
+                    option
+                        .name("rise_ms")
+                        .description("rise and fall time (ms)")
+                        .kind(CommandOptionType::Number)
+                        .min_number_value(0.0)
+                        .max_number_value(50.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("envelope")
+                        .description("envelope shape")
+                        .kind(CommandOptionType::String)
+                        .required(false);
+                    for e in Envelope::ALL {
+                        option.add_string_choice(e.name(), e.name());
+                    }
+                    option
+                })
+                .create_option(|option| {
+                    option
+                        .name("waveform")
+                        .description("tone timbre")
+                        .kind(CommandOptionType::String)
+                        .required(false);
+                    for w in Waveform::ALL {
+                        option.add_string_choice(w.name(), w.name());
+                    }
+                    option
+                })
+        })
+        .await
+        .context("command cw-tone registration failed")?;
+
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-volume")
+                .description("set your cw volume")
+                .create_option(|option| {
+                    option
+                        .name("volume")
+                        .description("volume, 0.0 ~ 1.0 (1.0 = default, full scale)")
+                        .kind(CommandOptionType::Number)
+                        .min_number_value(0.0)
+                        .max_number_value(1.0)
+                        .required(true)
+                })
+        })
+        .await
+        .context("command cw-volume registration failed")?;
+
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-pan")
+                .description("set your stereo position (omit to reset)")
+                .create_option(|option| {
+                    option
+                        .name("pan")
+                        .description("-1.0 (left) ~ 1.0 (right)")
+                        .kind(CommandOptionType::Number)
+                        .min_number_value(-1.0)
+                        .max_number_value(1.0)
+                        .required(false)
+                })
+        })
+        .await
+        .context("command cw-pan registration failed")?;
+
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-guild-volume")
+                .description("set cw volume of this server")
+                .dm_permission(false)
+                .default_member_permissions(serenity::model::Permissions::MANAGE_GUILD)
+                .create_option(|option| {
+                    option
+                        .name("volume")
+                        .description("volume, 0.0 ~ 1.0 (1.0 = default, full scale)")
+                        .kind(CommandOptionType::Number)
+                        .min_number_value(0.0)
+                        .max_number_value(1.0)
+                        .required(true)
+                })
+        })
+        .await
+        .context("command cw-guild-volume registration failed")?;
+
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-guild-queue")
+                .description("queue messages of this server instead of playing them together")
+                .dm_permission(false)
+                .default_member_permissions(serenity::model::Permissions::MANAGE_GUILD)
+                .create_option(|option| {
+                    option
+                        .name("enable")
+                        .description("play messages one at a time, in order")
+                        .kind(CommandOptionType::Boolean)
+                        .required(true)
+                })
+        })
+        .await
+        .context("command cw-guild-queue registration failed")?;
+
         Ok(())
     }
 }
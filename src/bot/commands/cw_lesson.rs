@@ -4,29 +4,12 @@ use serenity::model::prelude::application_command::ApplicationCommandInteraction
 use serenity::prelude::Context;
 use std::sync::{Arc, Mutex};
 
-use crate::{bot::BotStateMode, modes::lesson::LessonGen};
-
-pub fn get_lesson_gen(probset: &str) -> anyhow::Result<LessonGen> {
-    // TODO: use braces to support nesting
-    let (probset_name, probset_args_str) = probset.split_once(':').unwrap_or((probset, ""));
-
-    use crate::modes::lesson;
-
-    let gen: LessonGen = match probset_name {
-        "call_ja" => Box::new(lesson::callsign::JaCallsignGen {}),
-        "file" => Box::new(lesson::file::FileSourceGen::new(probset_args_str)?),
-        "nr_allja" => Box::new(lesson::allja_number::AllJANumberGen::new()),
-        "nr_acag" => Box::new(lesson::acag_number::ACAGNumberGen::new()),
-        "rand5_jp" => Box::new(lesson::japanese::JapaneseFiveCharGen {}),
-        _ => {
-            anyhow::bail!(
-                "unknown probset.\n".to_owned()
-                    + "available selections are: call_ja, file, nr_allja, nr_acag, rand5_jp"
-            )
-        }
-    };
-    Ok(gen)
-}
+use crate::{
+    bot::BotStateMode,
+    cw_audio::{Chirp, Effects, Envelope, Flutter, NoiseColor, NoiseConfig, Qsb, Tone, Waveform},
+    modes::lesson::{get_lesson_gen, LessonOptions},
+    morse::{CodeTable, Fist},
+};
 
 impl crate::bot::Bot {
     pub async fn run_command_lesson_start(
@@ -38,7 +21,22 @@ impl crate::bot::Bot {
         let mut max_speed = None;
         let mut min_freq = None;
         let mut max_freq = None;
+        let mut min_snr = None;
+        let mut max_snr = None;
+        let mut effective_speed = None;
         let mut probset = "call_ja".to_string();
+        let mut table = CodeTable::default();
+        let mut wabun_markers = true;
+        let mut effects = Effects::default();
+        let mut fist = Fist::default();
+        let mut seed = None;
+        let mut tone = Tone::default();
+        let mut notation = false;
+        let mut noise = NoiseConfig::new(0.0);
+        let mut qsb_depth = None;
+        let mut qsb_period = None;
+        let mut flutter = None;
+        let mut chirp = None;
 
         command
             .data
@@ -55,35 +53,100 @@ impl crate::bot::Bot {
                     "max_speed" => max_speed = Some(vf?),
                     "min_freq" => min_freq = Some(vf?),
                     "max_freq" => max_freq = Some(vf?),
+                    "min_snr" => min_snr = Some(vf?),
+                    "max_snr" => max_snr = Some(vf?),
+                    "effective_speed" => effective_speed = Some(vf?),
                     "probset" => probset = vs?.to_string(),
+                    "table" => table = vs?.parse()?,
+                    "wabun_markers" => wabun_markers = v.as_bool().context("value is not bool")?,
+                    "conditions" => effects = vs?.parse()?,
+                    "qsb_depth" => qsb_depth = Some(vf?),
+                    "qsb_period" => qsb_period = Some(vf?),
+                    "flutter" => flutter = Some(vf?),
+                    "chirp" => chirp = Some(vf?),
+                    "fist" => fist = vs?.parse()?,
+                    "rise_ms" => tone.rise_ms = vf?,
+                    "envelope" => tone.envelope = vs?.parse()?,
+                    "waveform" => tone.waveform = vs?.parse()?,
+                    "seed" => seed = Some(v.as_u64().context("value is not u64")?),
+                    "noise_color" => noise.color = vs?.parse()?,
+                    "crashes" => noise.crashes = vf?,
+                    "bandwidth" => noise.bandwidth = Some(vf?).filter(|&bw| bw > 0.0),
+                    "notation" => notation = v.as_bool().context("value is not bool")?,
                     _ => (),
                 };
                 Ok(())
             })?;
 
-        let min_speed = min_speed.unwrap_or(15.0_f32.min(max_speed.unwrap_or(std::f32::NAN)));
+        let min_speed = min_speed.unwrap_or(15.0_f32.min(max_speed.unwrap_or(f32::NAN)));
         let max_speed = max_speed.unwrap_or(20.0_f32.max(min_speed));
 
         anyhow::ensure!(min_speed <= max_speed, "min_speed > max_speed");
 
-        let min_freq = min_freq.unwrap_or(500.0_f32.min(max_freq.unwrap_or(std::f32::NAN)));
+        let min_freq = min_freq.unwrap_or(500.0_f32.min(max_freq.unwrap_or(f32::NAN)));
         let max_freq = max_freq.unwrap_or(1000.0_f32.max(min_freq));
 
         anyhow::ensure!(min_freq <= max_freq, "min_freq > max_freq");
 
+        // noise only when requested
+        let snr_range = match (min_snr, max_snr) {
+            (None, None) => None,
+            (min, max) => {
+                let min_snr = min.unwrap_or(0.0_f32.min(max.unwrap_or(f32::NAN)));
+                let max_snr = max.unwrap_or(20.0_f32.max(min_snr));
+                anyhow::ensure!(min_snr <= max_snr, "min_snr > max_snr");
+                Some(min_snr..=max_snr)
+            }
+        };
+
+        // each effect on its own overrides the preset; 0 turns it off
+        if qsb_depth.is_some() || qsb_period.is_some() {
+            let qsb = effects.qsb.clone().unwrap_or_else(Effects::qsb);
+            effects.qsb = Some(Qsb {
+                depth: qsb_depth.unwrap_or(qsb.depth),
+                period: qsb_period.unwrap_or(qsb.period),
+            })
+            .filter(|q| q.depth > 0.0);
+        }
+        if let Some(depth) = flutter {
+            let flutter = effects.flutter.clone().unwrap_or_else(Effects::flutter);
+            effects.flutter = (depth > 0.0).then_some(Flutter { depth, ..flutter });
+        }
+        if let Some(shift) = chirp {
+            let chirp = effects.chirp.clone().unwrap_or_else(Effects::chirp);
+            effects.chirp = (shift > 0.0).then_some(Chirp { shift, ..chirp });
+        }
+
         let speed_range = min_speed..=max_speed;
         let freq_range = min_freq..=max_freq;
 
         probset.make_ascii_lowercase();
-        let gen = get_lesson_gen(&probset)?;
+        let gen = get_lesson_gen(&probset, None)?;
 
         let gid = command.guild_id.context("not in guild")?;
+        let volume = crate::modes::normal::GuildConfig::load(&self.db, gid)
+            .await
+            .context("internal error")?
+            .volume;
         let state = Arc::new(Mutex::new(crate::modes::lesson::LessonModeState::new(
             speed_range,
             freq_range,
+            LessonOptions {
+                effective_speed,
+                snr_range,
+                noise,
+                effects,
+                fist: Fist { seed, ..fist },
+                tone,
+                table,
+                wabun_markers,
+                volume,
+                notation,
+            },
             gen,
         )));
-        crate::modes::lesson::start(ctx, gid, state.clone())
+        let txt_ch = self.get_call_txt_ch(gid.0)?;
+        crate::modes::lesson::start(ctx, gid, txt_ch, state.clone())
             .await
             .context("internal error")?;
         self.switch_mode(gid.0, BotStateMode::Lesson(state))?;
@@ -125,6 +188,14 @@ impl crate::bot::Bot {
                         .min_number_value(5.0)
                         .required(false)
                 })
+                .create_option(|option| {
+                    option
+                        .name("effective_speed")
+                        .description("effective speed, gaps are stretched (Farnsworth)")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Number)
+                        .min_number_value(1.0)
+                        .required(false)
+                })
                 .create_option(|option| {
                     option
                         .name("min_freq")
@@ -141,6 +212,150 @@ impl crate::bot::Bot {
                         .min_number_value(200.0)
                         .required(false)
                 })
+                .create_option(|option| {
+                    option
+                        .name("min_snr")
+                        .description("minimum signal to noise ratio in dB (adds band noise)")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Number)
+                        .min_number_value(-20.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("max_snr")
+                        .description("maximum signal to noise ratio in dB (adds band noise)")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Number)
+                        .min_number_value(-20.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("noise_color")
+                        .description("spectrum of the band noise")
+                        .kind(serenity::model::prelude::command::CommandOptionType::String)
+                        .required(false);
+                    for c in NoiseColor::ALL {
+                        option.add_string_choice(c.name(), c.name());
+                    }
+                    option
+                })
+                .create_option(|option| {
+                    option
+                        .name("crashes")
+                        .description("static crashes per second in the band noise (default 0.2)")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Number)
+                        .min_number_value(0.0)
+                        .max_number_value(10.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("bandwidth")
+                        .description("receiver filter width in Hz, 0 for no filter (default 500)")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Number)
+                        .min_number_value(0.0)
+                        .max_number_value(3000.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("conditions")
+                        .description("propagation conditions (fading, flutter, chirp)")
+                        .kind(serenity::model::prelude::command::CommandOptionType::String)
+                        .required(false);
+                    for p in Effects::PRESETS {
+                        option.add_string_choice(p, p);
+                    }
+                    option
+                })
+                .create_option(|option| {
+                    option
+                        .name("qsb_depth")
+                        .description("fading depth, 0 for none and 1 to fade out completely")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Number)
+                        .min_number_value(0.0)
+                        .max_number_value(1.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("qsb_period")
+                        .description("fading period in seconds (default 8)")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Number)
+                        .min_number_value(1.0)
+                        .max_number_value(60.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("flutter")
+                        .description("auroral flutter depth, 0 for none")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Number)
+                        .min_number_value(0.0)
+                        .max_number_value(1.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("chirp")
+                        .description("frequency shift at key down in Hz, 0 for none")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Number)
+                        .min_number_value(0.0)
+                        .max_number_value(300.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("fist")
+                        .description("sender profile (timing like a human operator)")
+                        .kind(serenity::model::prelude::command::CommandOptionType::String)
+                        .required(false);
+                    for p in Fist::PRESETS {
+                        option.add_string_choice(p, p);
+                    }
+                    option
+                })
+                .create_option(|option| {
+                    option
+                        .name("seed")
+                        .description(
+                            "random seed for the sender profile, to repeat the same timing",
+                        )
+                        .kind(serenity::model::prelude::command::CommandOptionType::Integer)
+                        .min_int_value(0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("rise_ms")
+                        .description("rise and fall time (ms)")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Number)
+                        .min_number_value(0.0)
+                        .max_number_value(50.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("envelope")
+                        .description("envelope shape")
+                        .kind(serenity::model::prelude::command::CommandOptionType::String)
+                        .required(false);
+                    for e in Envelope::ALL {
+                        option.add_string_choice(e.name(), e.name());
+                    }
+                    option
+                })
+                .create_option(|option| {
+                    option
+                        .name("waveform")
+                        .description("tone timbre")
+                        .kind(serenity::model::prelude::command::CommandOptionType::String)
+                        .required(false);
+                    for w in Waveform::ALL {
+                        option.add_string_choice(w.name(), w.name());
+                    }
+                    option
+                })
                 .create_option(|option| {
                     option
                         .name("probset")
@@ -148,6 +363,31 @@ impl crate::bot::Bot {
                         .kind(serenity::model::prelude::command::CommandOptionType::String)
                         .required(false)
                 })
+                .create_option(|option| {
+                    option
+                        .name("table")
+                        .description("code table used for letters")
+                        .kind(serenity::model::prelude::command::CommandOptionType::String)
+                        .required(false);
+                    for t in CodeTable::ALL {
+                        option.add_string_choice(t.name(), t.name());
+                    }
+                    option
+                })
+                .create_option(|option| {
+                    option
+                        .name("wabun_markers")
+                        .description("send ホレ / ラタ when switching between kana and letters (default: true)")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Boolean)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("notation")
+                        .description("post questions as text, to be answered in dot/dash notation")
+                        .kind(serenity::model::prelude::command::CommandOptionType::Boolean)
+                        .required(false)
+                })
         })
         .await
         .context("command cw-start-lesson registration failed")?;
@@ -0,0 +1,95 @@
+use anyhow::Context as _;
+use serenity::model::application::command::Command;
+use serenity::model::prelude::application_command::ApplicationCommandInteraction;
+use serenity::prelude::Context;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    bot::BotStateMode,
+    modes::{lesson::get_lesson_gen, sending::SendingModeState},
+};
+
+impl crate::bot::Bot {
+    pub async fn run_command_sending_start(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let mut probset = "call_ja".to_string();
+
+        for x in &command.data.options {
+            let v = x.value.as_ref().context("value empty")?;
+            if x.name == "probset" {
+                probset = v.as_str().context("value is not string")?.to_string();
+            }
+        }
+
+        probset.make_ascii_lowercase();
+        let gen = get_lesson_gen(&probset, None)?;
+
+        let gid = command.guild_id.context("not in guild")?;
+
+        // the listener would get the sink of the running one back when it is discarded
+        let mode = self.get_call_mode(gid.0)?;
+        anyhow::ensure!(
+            !matches!(
+                *mode
+                    .lock()
+                    .or_else(|_| anyhow::bail!("lock failed"))
+                    .context("internal error")?,
+                BotStateMode::Sending(_)
+            ),
+            "sending practice is already running. /cw-end-sending first"
+        );
+
+        let txt_ch = self.get_call_txt_ch(gid.0)?;
+        let listener = self.get_call_listener(gid.0)?;
+        let state = Arc::new(Mutex::new(SendingModeState::new(gen)));
+        crate::modes::sending::start(ctx, gid, txt_ch, listener, state.clone())
+            .await
+            .context("internal error")?;
+        self.switch_mode(gid.0, BotStateMode::Sending(state))?;
+
+        Ok("let's start sending practice. key the text shown in the voice channel".to_string())
+    }
+
+    pub async fn run_command_sending_end(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let r = self.switch_mode(
+            command.guild_id.context("no guild")?.0,
+            BotStateMode::Normal,
+        )?;
+
+        Ok(r)
+    }
+
+    pub async fn register_commands_cw_sending(&self, ctx: &Context) -> anyhow::Result<()> {
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-start-sending")
+                .description("start sending practice, graded from your keyed audio")
+                .create_option(|option| {
+                    option
+                        .name("probset")
+                        .description("problem set name (can be followed by colon and args)")
+                        .kind(serenity::model::prelude::command::CommandOptionType::String)
+                        .required(false)
+                })
+        })
+        .await
+        .context("command cw-start-sending registration failed")?;
+
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-end-sending")
+                .description("end sending practice")
+        })
+        .await
+        .context("command cw-end-sending registration failed")?;
+
+        Ok(())
+    }
+}
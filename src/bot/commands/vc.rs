@@ -3,6 +3,9 @@ use serenity::model::application::command::Command;
 use serenity::model::prelude::application_command::ApplicationCommandInteraction;
 use serenity::model::prelude::command::CommandOptionType;
 use serenity::prelude::Context;
+use std::sync::Arc;
+
+const QUEUE_TEXT_CHARS: usize = 40; // of each message listed in /cw-queue
 
 fn get_ch(
     cmd: &serenity::model::prelude::application_command::ApplicationCommandInteraction,
@@ -44,6 +47,7 @@ impl crate::bot::Bot {
             let mut handler = handler.0.lock().await;
             handler.deafen(true).await.context("deafen failed")?;
         }
+        self.get_call_listener(gid.0)?.register(&handler.0).await;
 
         Ok("got it!".to_string())
     }
@@ -69,6 +73,182 @@ impl crate::bot::Bot {
         Ok("bye!".to_string())
     }
 
+    pub async fn run_command_skimmer(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let enable = command
+            .data
+            .options
+            .iter()
+            .find(|opt| opt.name == "enable")
+            .and_then(|opt| opt.value.as_ref())
+            .and_then(|v| v.as_bool())
+            .context("no argument")?;
+        let gid = command.guild_id.context("not in guild")?;
+
+        // sending practice listens to the call itself
+        let mode = self.get_call_mode(gid.0)?;
+        anyhow::ensure!(
+            !matches!(
+                *mode
+                    .lock()
+                    .or_else(|_| anyhow::bail!("lock failed"))
+                    .context("internal error")?,
+                crate::bot::BotStateMode::Sending(_)
+            ),
+            "not available during sending practice"
+        );
+
+        let man = songbird::get(ctx).await.expect("init songbird").clone();
+        let call = man.get(gid).context("not in call")?;
+        let listener = self.get_call_listener(gid.0)?;
+        if enable {
+            let txt_ch = self.get_call_txt_ch(gid.0)?;
+            let sink = crate::modes::skimmer::Post::new(ctx.http.clone(), txt_ch);
+            listener.start(&call, Arc::new(sink)).await?;
+            Ok("listening! CW sent in the voice channel is posted here".to_string())
+        } else {
+            listener.stop(&call).await?;
+            Ok("stopped listening".to_string())
+        }
+    }
+
+    pub async fn run_command_play(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let gid = command.guild_id.context("not in guild")?;
+        let mut cfg = crate::modes::normal::UserConfig::load(&self.db, command.user.id).await?;
+        let mut text = None;
+        let mut repeat = 1;
+
+        for x in &command.data.options {
+            let v = x.value.as_ref().context("value empty")?;
+            match x.name.as_str() {
+                "str" => text = Some(v.as_str().context("value is not string")?.to_string()),
+                "speed" => cfg.speed = v.as_f64().context("value is not f64")? as f32,
+                "freq" => cfg.freq = v.as_f64().context("value is not f64")? as f32,
+                "repeat" => repeat = v.as_u64().context("value is not u64")? as usize,
+                _ => (),
+            }
+        }
+        let text = text.context("no argument")?;
+
+        let man = songbird::get(ctx).await.expect("init songbird").clone();
+        man.get(gid).context("not in call")?;
+
+        let s = vec![text; repeat].join(" ");
+        if let Some(track) =
+            crate::modes::normal::play(ctx, gid, &s, &cfg, &self.db, &command.user.name).await?
+        {
+            self.add_call_track(gid.0, track)?;
+        }
+
+        Ok("playing".to_string())
+    }
+
+    pub async fn run_command_queue(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let clear = command
+            .data
+            .options
+            .iter()
+            .find(|opt| opt.name == "clear")
+            .and_then(|opt| opt.value.as_ref())
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let man = songbird::get(ctx).await.expect("init songbird").clone();
+        let call = man
+            .get(command.guild_id.context("not in guild")?)
+            .context("not in call")?;
+        let handler = call.lock().await;
+        let queue = handler.queue();
+
+        // all but the one playing
+        if clear {
+            queue.modify_queue(|q| {
+                if q.len() > 1 {
+                    q.drain(1..).for_each(|t| {
+                        let _ = t.stop();
+                    });
+                }
+            });
+        }
+
+        let tracks = queue.current_queue();
+        if tracks.is_empty() {
+            return Ok("nothing queued".to_string());
+        }
+        Ok(tracks
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let m = t.metadata();
+                let text = m.title.as_deref().unwrap_or_default().replace('`', "");
+                let text = match text.char_indices().nth(QUEUE_TEXT_CHARS) {
+                    Some((n, _)) => format!("{}…", &text[..n]),
+                    None => text,
+                };
+                let head = if i == 0 {
+                    "▶".to_string()
+                } else {
+                    format!("{}.", i)
+                };
+                format!(
+                    "{} {}: `{}`",
+                    head,
+                    m.artist.as_deref().unwrap_or("someone"),
+                    text
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    pub async fn run_command_skip(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let man = songbird::get(ctx).await.expect("init songbird").clone();
+        let call = man
+            .get(command.guild_id.context("not in guild")?)
+            .context("not in call")?;
+        let handler = call.lock().await;
+
+        if handler.queue().is_empty() {
+            return Ok("nothing queued".to_string());
+        }
+        handler.queue().skip().context("skip failed")?;
+        Ok("skipped".to_string())
+    }
+
+    // stops the queue; lesson questions are left to the lesson
+    pub async fn run_command_stop(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+    ) -> anyhow::Result<String> {
+        let gid = command.guild_id.context("not in guild")?;
+        let man = songbird::get(ctx).await.expect("init songbird").clone();
+        let call = man.get(gid).context("not in call")?;
+        let handler = call.lock().await;
+
+        handler.queue().stop();
+        // those already ended fail to stop, which is fine
+        for track in self.take_call_tracks(gid.0)? {
+            let _ = track.stop();
+        }
+        Ok("stopped".to_string())
+    }
+
     pub async fn register_commands_vc(&self, ctx: &Context) -> anyhow::Result<()> {
         Command::create_global_application_command(&ctx.http, |command| {
             command
@@ -93,6 +273,21 @@ impl crate::bot::Bot {
         .await
         .context("command cw-leave registration failed")?;
 
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-skimmer")
+                .description("decode CW sent in the voice channel and post it as text")
+                .create_option(|option| {
+                    option
+                        .name("enable")
+                        .description("listen to the voice channel")
+                        .kind(CommandOptionType::Boolean)
+                        .required(true)
+                })
+        })
+        .await
+        .context("command cw-skimmer registration failed")?;
+
         Command::create_global_application_command(&ctx.http, |command| {
             command
                 .name("cw-play")
@@ -104,10 +299,66 @@ impl crate::bot::Bot {
                         .kind(CommandOptionType::String)
                         .required(true)
                 })
+                .create_option(|option| {
+                    option
+                        .name("speed")
+                        .description("speed in WPM, your /cw-speed if omitted")
+                        .kind(CommandOptionType::Number)
+                        .min_number_value(5.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("freq")
+                        .description("frequency in Hz, your /cw-freq if omitted")
+                        .kind(CommandOptionType::Number)
+                        .min_number_value(10.0)
+                        .required(false)
+                })
+                .create_option(|option| {
+                    option
+                        .name("repeat")
+                        .description("times to play")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .max_int_value(10)
+                        .required(false)
+                })
         })
         .await
         .context("command cw-play registration failed")?;
 
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-queue")
+                .description("list messages waiting to be played")
+                .create_option(|option| {
+                    option
+                        .name("clear")
+                        .description("remove the waiting messages, the one playing goes on")
+                        .kind(CommandOptionType::Boolean)
+                        .required(false)
+                })
+        })
+        .await
+        .context("command cw-queue registration failed")?;
+
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-skip")
+                .description("skip the message playing, to the next one queued")
+        })
+        .await
+        .context("command cw-skip registration failed")?;
+
+        Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("cw-stop")
+                .description("stop playback and clear the queue")
+        })
+        .await
+        .context("command cw-stop registration failed")?;
+
         Ok(())
     }
 }
@@ -16,6 +16,7 @@ pub enum BotStateMode {
     #[default]
     Normal,
     Lesson(Arc<Mutex<crate::modes::lesson::LessonModeState>>),
+    Sending(Arc<Mutex<crate::modes::sending::SendingModeState>>),
 }
 
 impl BotStateMode {
@@ -28,6 +29,10 @@ impl BotStateMode {
                 log::info!("terminating callsign lesson");
                 crate::modes::lesson::end(s.clone()).ok()
             }
+            BotStateMode::Sending(s) => {
+                log::info!("terminating sending practice");
+                crate::modes::sending::end(s.clone()).ok()
+            }
         }
     }
 }
@@ -35,6 +40,9 @@ impl BotStateMode {
 struct BotState {
     txt_ch: serenity::model::id::ChannelId,
     mode: Arc<Mutex<BotStateMode>>,
+    listener: crate::modes::skimmer::Listener,
+    // played outside the queue, to be stopped by /cw-stop
+    tracks: Arc<Mutex<Vec<songbird::tracks::TrackHandle>>>,
 }
 
 impl Clone for BotState {
@@ -42,6 +50,8 @@ impl Clone for BotState {
         BotState {
             txt_ch: self.txt_ch,
             mode: self.mode.clone(),
+            listener: self.listener.clone(),
+            tracks: self.tracks.clone(),
         }
     }
 }
@@ -79,6 +89,8 @@ impl Bot {
             BotState {
                 txt_ch: ch,
                 mode: Arc::new(Mutex::new(BotStateMode::Normal)),
+                listener: crate::modes::skimmer::Listener::new(self.db.clone()),
+                tracks: Arc::new(Mutex::new(Vec::new())),
             },
         );
 
@@ -129,6 +141,76 @@ impl Bot {
         Ok(states.get(&guild_id).context("not in call")?.mode.clone())
     }
 
+    pub fn get_call_listener(
+        &self,
+        guild_id: u64,
+    ) -> anyhow::Result<crate::modes::skimmer::Listener> {
+        let states = self
+            .states
+            .lock()
+            .or_else(|_| anyhow::bail!("lock failed"))
+            .context("internal error")?;
+
+        Ok(states
+            .get(&guild_id)
+            .context("not in call")?
+            .listener
+            .clone())
+    }
+
+    pub fn add_call_track(
+        &self,
+        guild_id: u64,
+        track: songbird::tracks::TrackHandle,
+    ) -> anyhow::Result<()> {
+        let states = self
+            .states
+            .lock()
+            .or_else(|_| anyhow::bail!("lock failed"))
+            .context("internal error")?;
+
+        let tracks = &states.get(&guild_id).context("not in call")?.tracks;
+        let mut v = tracks
+            .lock()
+            .or_else(|_| anyhow::bail!("lock failed"))
+            .context("internal error")?;
+
+        // fails only if the track has already ended
+        if track
+            .add_event(
+                songbird::Event::Track(songbird::TrackEvent::End),
+                TrackEnd {
+                    tracks: tracks.clone(),
+                },
+            )
+            .is_ok()
+        {
+            v.push(track);
+        }
+        Ok(())
+    }
+
+    // the tracks still playing, taken out
+    pub fn take_call_tracks(
+        &self,
+        guild_id: u64,
+    ) -> anyhow::Result<Vec<songbird::tracks::TrackHandle>> {
+        let states = self
+            .states
+            .lock()
+            .or_else(|_| anyhow::bail!("lock failed"))
+            .context("internal error")?;
+
+        let mut tracks = states
+            .get(&guild_id)
+            .context("not in call")?
+            .tracks
+            .lock()
+            .or_else(|_| anyhow::bail!("lock failed"))
+            .context("internal error")?;
+        Ok(std::mem::take(&mut *tracks))
+    }
+
     pub fn erase_call_state(&self, guild_id: u64) -> anyhow::Result<()> {
         let mut states = self
             .states
@@ -149,6 +231,26 @@ impl Bot {
     }
 }
 
+// drops the handle of a track played outside the queue when it ends
+struct TrackEnd {
+    tracks: Arc<Mutex<Vec<songbird::tracks::TrackHandle>>>,
+}
+
+#[async_trait]
+impl songbird::EventHandler for TrackEnd {
+    async fn act(&self, ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
+        let songbird::EventContext::Track(ended) = ctx else {
+            return None;
+        };
+        let Ok(mut tracks) = self.tracks.lock() else {
+            log::error!("lock failed");
+            return None;
+        };
+        tracks.retain(|t| ended.iter().all(|(_, h)| h.uuid() != t.uuid()));
+        None
+    }
+}
+
 #[async_trait]
 impl EventHandler for Bot {
     // Botが起動したときに走る処理
@@ -161,6 +263,7 @@ impl EventHandler for Bot {
         let _ = self.register_commands_vc(&ctx).await;
         let _ = self.register_commands_cw(&ctx).await;
         let _ = self.register_commands_cw_lesson(&ctx).await;
+        let _ = self.register_commands_cw_sending(&ctx).await;
         log::info!("commands registered");
     }
 
@@ -172,10 +275,24 @@ impl EventHandler for Bot {
                 "neko" => self.run_command_neko(&command.data.options),
                 "cw-join" => self.run_command_join(&ctx, &command).await,
                 "cw-leave" => self.run_command_leave(&ctx, &command).await,
+                "cw-play" => self.run_command_play(&ctx, &command).await,
+                "cw-queue" => self.run_command_queue(&ctx, &command).await,
+                "cw-skip" => self.run_command_skip(&ctx, &command).await,
+                "cw-stop" => self.run_command_stop(&ctx, &command).await,
+                "cw-skimmer" => self.run_command_skimmer(&ctx, &command).await,
                 "cw-speed" => self.run_command_speed(&ctx, &command).await,
                 "cw-freq" => self.run_command_freq(&ctx, &command).await,
+                "cw-table" => self.run_command_table(&ctx, &command).await,
+                "cw-fist" => self.run_command_fist(&ctx, &command).await,
+                "cw-tone" => self.run_command_tone(&ctx, &command).await,
+                "cw-volume" => self.run_command_volume(&ctx, &command).await,
+                "cw-pan" => self.run_command_pan(&ctx, &command).await,
+                "cw-guild-volume" => self.run_command_guild_volume(&ctx, &command).await,
+                "cw-guild-queue" => self.run_command_guild_queue(&ctx, &command).await,
                 "cw-start-lesson" => self.run_command_lesson_start(&ctx, &command).await,
                 "cw-end-lesson" => self.run_command_lesson_end(&ctx, &command).await,
+                "cw-start-sending" => self.run_command_sending_start(&ctx, &command).await,
+                "cw-end-sending" => self.run_command_sending_end(&ctx, &command).await,
                 _ => Ok("not implemented :(".to_string()),
             }
             .unwrap_or_else(|e| {
@@ -231,8 +348,12 @@ impl EventHandler for Bot {
         // NOTE: actually clone not needed but compiler complains: https://github.com/rust-lang/rust/issues/104883
         let mode = (*mode.lock().unwrap()).clone();
         match mode {
-            BotStateMode::Normal => {
-                crate::modes::normal::on_message(&ctx, &message, &self.db).await
+            // texts are sent by key in sending practice, so messages are played as usual
+            BotStateMode::Normal | BotStateMode::Sending(_) => {
+                match crate::modes::normal::on_message(&ctx, &message, &self.db).await {
+                    Ok(Some(track)) => self.add_call_track(gid.0, track),
+                    r => r.map(|_| ()),
+                }
             }
 
             BotStateMode::Lesson(s) => {
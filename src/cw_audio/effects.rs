@@ -0,0 +1,205 @@
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+use rand::Rng;
+
+// slow fading
+#[derive(Debug, Clone, PartialEq)]
+pub struct Qsb {
+    pub depth: f32,  // 0.0 ~ 1.0, 1.0 fades out completely
+    pub period: f32, // in seconds
+}
+
+// auroral flutter, fast amplitude and phase modulation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flutter {
+    pub rate: f32,      // in Hz
+    pub depth: f32,     // 0.0 ~ 1.0
+    pub phase_dev: f32, // in radians
+}
+
+// frequency shift at key-down, decaying exponentially
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chirp {
+    pub shift: f32, // in Hz
+    pub decay: f32, // time constant in seconds
+}
+
+/// propagation and transmitter effects layered on the oscillator
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Effects {
+    pub qsb: Option<Qsb>,
+    pub flutter: Option<Flutter>,
+    pub chirp: Option<Chirp>,
+    pub seed: Option<u64>,
+}
+
+impl Effects {
+    pub const PRESETS: [&'static str; 5] = ["clean", "qsb", "flutter", "chirp", "all"];
+
+    pub fn qsb() -> Qsb {
+        Qsb {
+            depth: 0.8,
+            period: 8.0,
+        }
+    }
+
+    pub fn flutter() -> Flutter {
+        Flutter {
+            rate: 15.0,
+            depth: 0.6,
+            phase_dev: 1.0,
+        }
+    }
+
+    pub fn chirp() -> Chirp {
+        Chirp {
+            shift: 60.0,
+            decay: 0.015,
+        }
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.qsb.is_none() && self.flutter.is_none() && self.chirp.is_none()
+    }
+}
+
+impl FromStr for Effects {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let e = Effects::default();
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "clean" => e,
+            "qsb" => Effects {
+                qsb: Some(Effects::qsb()),
+                ..e
+            },
+            "flutter" => Effects {
+                flutter: Some(Effects::flutter()),
+                ..e
+            },
+            "chirp" => Effects {
+                chirp: Some(Effects::chirp()),
+                ..e
+            },
+            "all" => Effects {
+                qsb: Some(Effects::qsb()),
+                flutter: Some(Effects::flutter()),
+                chirp: Some(Effects::chirp()),
+                ..e
+            },
+            _ => anyhow::bail!(
+                "unknown conditions: {}\navailable selections are: {}",
+                s,
+                Effects::PRESETS.join(", ")
+            ),
+        })
+    }
+}
+
+/*
+    effects as functions of time, so that the audio stays seekable
+    random phases make each playback sound a little different
+*/
+pub(crate) struct Modulator {
+    effects: Effects,
+    phases: [f32; 8],
+}
+
+impl Modulator {
+    pub fn new(effects: &Effects) -> Self {
+        let mut rng = crate::util::rng(effects.seed);
+        Self {
+            effects: effects.clone(),
+            phases: std::array::from_fn(|_| rng.gen_range(0.0..2.0 * PI)),
+        }
+    }
+
+    // -1.0 ~ 1.0, irregular around rate
+    fn wobble(&self, t: f32, rate: f32, phases: &[f32]) -> f32 {
+        [0.7, 1.0, 1.3]
+            .iter()
+            .zip(phases)
+            .map(|(r, p)| (2.0 * PI * rate * r * t + p).sin())
+            .sum::<f32>()
+            / 3.0
+    }
+
+    // amplitude at t seconds from the start
+    pub fn gain(&self, t: f32) -> f32 {
+        let mut g = 1.0;
+        if let Some(q) = &self.effects.qsb {
+            let w = 0.6 * (2.0 * PI * t / q.period + self.phases[0]).cos()
+                + 0.4 * (2.0 * PI * t / (1.7 * q.period) + self.phases[1]).cos();
+            g *= 1.0 - q.depth * 0.5 * (1.0 - w);
+        }
+        if let Some(f) = &self.effects.flutter {
+            g *= 1.0 - f.depth * 0.5 * (1.0 + self.wobble(t, f.rate, &self.phases[2..5]));
+        }
+        g
+    }
+
+    // phase offset at t seconds from the start, and t_mark seconds from the key-down
+    pub fn phase(&self, t: f32, t_mark: f32) -> f32 {
+        let mut p = 0.0;
+        if let Some(f) = &self.effects.flutter {
+            p += f.phase_dev * self.wobble(t, f.rate, &self.phases[5..8]);
+        }
+        if let Some(c) = &self.effects.chirp {
+            // integral of shift * exp(-t / decay)
+            p += 2.0 * PI * c.shift * c.decay * (1.0 - (-t_mark / c.decay).exp());
+        }
+        p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        for p in Effects::PRESETS {
+            let e: Effects = p.parse().unwrap();
+            assert_eq!(e.is_clean(), p == "clean");
+        }
+        assert!("foo".parse::<Effects>().is_err());
+    }
+
+    #[test]
+    fn test_qsb_depth() {
+        let m = Modulator::new(&Effects {
+            qsb: Some(Qsb {
+                depth: 0.9,
+                period: 2.0,
+            }),
+            seed: Some(1),
+            ..Default::default()
+        });
+        let g = (0..2000)
+            .map(|i| m.gain(i as f32 * 0.01))
+            .collect::<Vec<_>>();
+        let min = g.iter().cloned().fold(f32::MAX, f32::min);
+        let max = g.iter().cloned().fold(f32::MIN, f32::max);
+        assert!((0.1 - 1e-4..0.3).contains(&min), "{min}");
+        assert!((0.9..=1.0 + 1e-4).contains(&max), "{max}");
+        assert_eq!(m.phase(1.0, 0.1), 0.0);
+    }
+
+    #[test]
+    fn test_chirp() {
+        let m = Modulator::new(&Effects {
+            chirp: Some(Chirp {
+                shift: 50.0,
+                decay: 0.01,
+            }),
+            ..Default::default()
+        });
+        let freq = |t: f32| (m.phase(0.0, t + 1e-4) - m.phase(0.0, t)) / 1e-4 / (2.0 * PI);
+        // shifted at key-down, settled later
+        assert!((freq(0.0) - 50.0).abs() < 1.0);
+        assert!(freq(0.1).abs() < 0.1);
+        assert_eq!(m.gain(0.3), 1.0);
+    }
+}
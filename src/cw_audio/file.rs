@@ -0,0 +1,240 @@
+use std::io::Write;
+
+// container of an audio file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FileFormat {
+    #[default]
+    Wav,
+    Raw, // headerless, little endian
+    Au,  // Sun audio, big endian
+}
+
+impl FileFormat {
+    pub const ALL: [FileFormat; 3] = [FileFormat::Wav, FileFormat::Raw, FileFormat::Au];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileFormat::Wav => "wav",
+            FileFormat::Raw => "raw",
+            FileFormat::Au => "au",
+        }
+    }
+
+    // guesses from the extension of the path
+    pub fn from_path(path: &std::path::Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "wav" | "wave" => Some(FileFormat::Wav),
+            "raw" | "pcm" => Some(FileFormat::Raw),
+            "au" | "snd" => Some(FileFormat::Au),
+            _ => None,
+        }
+    }
+}
+
+// encoding of each sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SampleFormat {
+    U8, // signed in AU
+    #[default]
+    S16,
+    S24,
+    F32,
+}
+
+impl SampleFormat {
+    pub const ALL: [SampleFormat; 4] = [
+        SampleFormat::U8,
+        SampleFormat::S16,
+        SampleFormat::S24,
+        SampleFormat::F32,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SampleFormat::U8 => "8",
+            SampleFormat::S16 => "16",
+            SampleFormat::S24 => "24",
+            SampleFormat::F32 => "float",
+        }
+    }
+
+    pub fn bytes(&self) -> usize {
+        match self {
+            SampleFormat::U8 => 1,
+            SampleFormat::S16 => 2,
+            SampleFormat::S24 => 3,
+            SampleFormat::F32 => 4,
+        }
+    }
+
+    // little endian
+    fn encode(&self, x: f32, out: &mut Vec<u8>) {
+        let x = x.clamp(-1.0, 1.0);
+        match self {
+            SampleFormat::U8 => out.push(((x * 127.0).round() as i32 + 128) as u8),
+            SampleFormat::S16 => {
+                out.extend_from_slice(&((x * i16::MAX as f32).round() as i16).to_le_bytes())
+            }
+            SampleFormat::S24 => {
+                out.extend_from_slice(&((x * 8388607.0).round() as i32).to_le_bytes()[..3])
+            }
+            SampleFormat::F32 => out.extend_from_slice(&x.to_le_bytes()),
+        }
+    }
+}
+
+crate::util::impl_named!(FileFormat, "file format");
+
+crate::util::impl_named!(SampleFormat, "sample format");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioFile {
+    pub format: FileFormat,
+    pub sample: SampleFormat,
+    pub srate: usize,
+    pub channels: usize,
+}
+
+impl AudioFile {
+    pub fn new(srate: usize) -> Self {
+        Self {
+            format: FileFormat::Wav,
+            sample: SampleFormat::S16,
+            srate,
+            channels: 1,
+        }
+    }
+
+    // samples are interleaved when there are multiple channels
+    pub fn write(&self, w: &mut impl Write, samples: &[f32]) -> std::io::Result<()> {
+        if self.format == FileFormat::Wav {
+            return self.write_wav(w, samples);
+        }
+
+        let mut data = Vec::with_capacity(samples.len() * self.sample.bytes());
+        samples
+            .iter()
+            .for_each(|&x| self.sample.encode(x, &mut data));
+
+        if self.format == FileFormat::Au {
+            w.write_all(&self.au_header(data.len()))?;
+            // big endian, and 8 bit samples are signed
+            match self.sample {
+                SampleFormat::U8 => data.iter_mut().for_each(|b| *b ^= 0x80),
+                s => data.chunks_mut(s.bytes()).for_each(|c| c.reverse()),
+            }
+        }
+        w.write_all(&data)?;
+        w.flush()
+    }
+
+    // hound seeks back to fill in the lengths, so it is written in memory first
+    fn write_wav(&self, w: &mut impl Write, samples: &[f32]) -> std::io::Result<()> {
+        let spec = hound::WavSpec {
+            channels: self.channels as u16,
+            sample_rate: self.srate as u32,
+            bits_per_sample: (self.sample.bytes() * 8) as u16,
+            sample_format: if self.sample == SampleFormat::F32 {
+                hound::SampleFormat::Float
+            } else {
+                hound::SampleFormat::Int
+            },
+        };
+        let io_error = |e| match e {
+            hound::Error::IoError(e) => e,
+            e => std::io::Error::new(std::io::ErrorKind::InvalidInput, e),
+        };
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        let mut wav = hound::WavWriter::new(&mut buf, spec).map_err(io_error)?;
+        for &x in samples {
+            let x = x.clamp(-1.0, 1.0);
+            match self.sample {
+                SampleFormat::U8 => wav.write_sample((x * 127.0).round() as i8),
+                SampleFormat::S16 => wav.write_sample((x * i16::MAX as f32).round() as i16),
+                SampleFormat::S24 => wav.write_sample((x * 8388607.0).round() as i32),
+                SampleFormat::F32 => wav.write_sample(x),
+            }
+            .map_err(io_error)?;
+        }
+        wav.finalize().map_err(io_error)?;
+
+        w.write_all(buf.get_ref())?;
+        w.flush()
+    }
+
+    fn au_header(&self, len: usize) -> Vec<u8> {
+        let encoding: u32 = match self.sample {
+            SampleFormat::U8 => 2,
+            SampleFormat::S16 => 3,
+            SampleFormat::S24 => 4,
+            SampleFormat::F32 => 6,
+        };
+        [
+            0x2e73_6e64, // ".snd"
+            24,
+            len as u32,
+            encoding,
+            self.srate as u32,
+            self.channels as u32,
+        ]
+        .iter()
+        .flat_map(|x: &u32| x.to_be_bytes())
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: [f32; 5] = [0.0, 0.5, -0.5, 1.0, -1.0];
+
+    #[test]
+    fn test_wav() {
+        for sample in SampleFormat::ALL {
+            let file = AudioFile {
+                sample,
+                ..AudioFile::new(44100)
+            };
+            let mut v = Vec::new();
+            file.write(&mut v, &SAMPLES).unwrap();
+
+            let mut r = hound::WavReader::new(std::io::Cursor::new(v)).unwrap();
+            assert_eq!(r.spec().sample_rate, 44100);
+            assert_eq!(r.spec().bits_per_sample as usize, sample.bytes() * 8);
+            let read: Vec<f32> = match sample {
+                SampleFormat::F32 => r.samples::<f32>().map(|s| s.unwrap()).collect(),
+                _ => {
+                    let max = (1 << (sample.bytes() * 8 - 1)) as f32;
+                    r.samples::<i32>()
+                        .map(|s| s.unwrap() as f32 / max)
+                        .collect()
+                }
+            };
+            for (a, b) in read.iter().zip(SAMPLES) {
+                assert!((a - b).abs() < 0.01, "{sample}: {a} != {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_au() {
+        let file = AudioFile {
+            format: FileFormat::Au,
+            ..AudioFile::new(8000)
+        };
+        let mut v = Vec::new();
+        file.write(&mut v, &SAMPLES).unwrap();
+        assert_eq!(&v[..4], b".snd");
+        assert_eq!(v.len(), 24 + SAMPLES.len() * 2);
+        assert_eq!(&v[26..28], &0x4000u16.to_be_bytes()); // 0.5
+        assert_eq!(&v[30..32], &0x7fffu16.to_be_bytes()); // 1.0
+
+        assert_eq!(
+            FileFormat::from_path("a/b.SND".as_ref()),
+            Some(FileFormat::Au)
+        );
+        assert_eq!("float".parse::<SampleFormat>().unwrap(), SampleFormat::F32);
+    }
+}
@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use songbird::input::{reader::MediaSource, Input};
+
+use super::CWAudioPCM;
+
+/// one signal in a pileup
+#[derive(Debug, Clone, PartialEq)]
+pub struct Station {
+    pub text: String,
+    pub wpm: f32,
+    pub freq_offset: f32, // in Hz, from the base frequency
+    pub amplitude: f32,
+    pub delay: Duration, // from the start of the mix
+}
+
+impl Station {
+    pub fn new(text: &str, wpm: f32) -> Self {
+        Self {
+            text: text.to_string(),
+            wpm,
+            freq_offset: 0.0,
+            amplitude: 1.0,
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+struct Track {
+    pcm: CWAudioPCM,
+    amplitude: f32,
+    delay: usize, // in samples
+}
+
+/// several CW signals mixed into one source
+pub struct CWMixer {
+    tracks: Vec<Track>,
+    pos: usize, // in samples
+    srate: usize,
+    buf: Vec<f32>,
+}
+
+impl CWMixer {
+    pub fn new(srate: usize) -> Self {
+        Self {
+            tracks: Vec::new(),
+            pos: 0,
+            srate,
+            buf: Vec::new(),
+        }
+    }
+
+    // pcm must have the same sample rate as the mixer
+    pub fn add(&mut self, pcm: CWAudioPCM, amplitude: f32, delay: Duration) {
+        self.tracks.push(Track {
+            pcm,
+            amplitude,
+            delay: (delay.as_secs_f64() * self.srate as f64).round() as usize,
+        });
+    }
+
+    pub fn add_station(&mut self, station: &Station, base_freq: f32) {
+        let pcm = CWAudioPCM::new(
+            station.text.clone(),
+            station.wpm,
+            base_freq + station.freq_offset,
+            self.srate,
+        );
+        self.add(pcm, station.amplitude, station.delay);
+    }
+
+    pub fn from_stations(stations: &[Station], base_freq: f32, srate: usize) -> Self {
+        let mut mixer = Self::new(srate);
+        stations
+            .iter()
+            .for_each(|s| mixer.add_station(s, base_freq));
+        mixer
+    }
+
+    // length in samples
+    pub fn len_samples(&self) -> usize {
+        self.tracks
+            .iter()
+            .map(|t| t.delay + t.pcm.len_samples())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.len_samples() as f64 / self.srate as f64)
+    }
+
+    pub fn seek_sample(&mut self, pos: usize) {
+        self.pos = pos.min(self.len_samples());
+        for t in &mut self.tracks {
+            t.pcm.seek_sample(self.pos.saturating_sub(t.delay));
+        }
+    }
+
+    // sums the tracks, each from its delay, into out; fewer than asked only at the end
+    pub fn read_samples(&mut self, out: &mut [f32]) -> usize {
+        let n = out.len().min(self.len_samples() - self.pos);
+        let out = &mut out[..n];
+        out.iter_mut().for_each(|x| *x = 0.0);
+
+        for t in &mut self.tracks {
+            // the part of this track overlapping [pos, pos + n)
+            let skip = t.delay.saturating_sub(self.pos).min(n);
+            self.buf.resize(n - skip, 0.0);
+            // a read may stop at an event boundary
+            let mut m = 0;
+            while m < self.buf.len() {
+                match t.pcm.read_samples(&mut self.buf[m..]) {
+                    0 => break,
+                    r => m += r,
+                }
+            }
+            out[skip..skip + m]
+                .iter_mut()
+                .zip(&self.buf)
+                .for_each(|(x, y)| *x += y * t.amplitude);
+        }
+
+        out.iter_mut().for_each(|x| *x = x.clamp(-1.0, 1.0));
+        self.pos += n;
+        n
+    }
+
+    // mono, played at 48kHz whatever rate the mixer was made with
+    pub fn to_input(self) -> Input {
+        Input::float_pcm(
+            false,
+            songbird::input::reader::Reader::Extension(std::boxed::Box::new(self)),
+        )
+    }
+}
+
+impl MediaSource for CWMixer {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+    fn byte_len(&self) -> Option<u64> {
+        Some(self.len_samples() as u64 * 4)
+    }
+}
+
+impl std::io::Read for CWMixer {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(super::read_f32(buf, |s| self.read_samples(s)))
+    }
+}
+
+impl std::io::Seek for CWMixer {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        use std::io::SeekFrom;
+        let pos = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => (self.len_samples() as u64 * 4).checked_add_signed(d),
+            SeekFrom::Current(d) => (self.pos as u64 * 4).checked_add_signed(d),
+        }
+        .ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "seek before start")
+        })?;
+
+        self.seek_sample((pos / 4) as usize);
+        Ok(self.pos as u64 * 4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::read_all;
+
+    #[test]
+    fn test_mixer() {
+        let srate = 8000;
+        let stations = [
+            Station {
+                amplitude: 0.5,
+                ..Station::new("JA1ABC", 25.0)
+            },
+            Station {
+                freq_offset: 300.0,
+                amplitude: 0.3,
+                delay: Duration::from_millis(700),
+                ..Station::new("JH1XYZ", 18.0)
+            },
+        ];
+        let mut mixer = CWMixer::from_stations(&stations, 600.0, srate);
+        let mixed = read_all(|b| mixer.read_samples(b));
+        assert_eq!(mixed.len(), mixer.len_samples());
+
+        let mut pcm = CWAudioPCM::new("JA1ABC".into(), 25.0, 600.0, srate);
+        let a = read_all(|buf| pcm.read_samples(buf));
+        let mut pcm = CWAudioPCM::new("JH1XYZ".into(), 18.0, 900.0, srate);
+        let b = read_all(|buf| pcm.read_samples(buf));
+        assert_eq!(mixed.len(), (5600 + b.len()).max(a.len()));
+
+        for (i, x) in mixed.iter().enumerate() {
+            let y = a.get(i).unwrap_or(&0.0) * 0.5
+                + i.checked_sub(5600).and_then(|j| b.get(j)).unwrap_or(&0.0) * 0.3;
+            assert!((x - y).abs() < 1e-6, "{i}");
+        }
+
+        mixer.seek_sample(3000);
+        assert_eq!(read_all(|b| mixer.read_samples(b)), mixed[3000..]);
+        mixer.seek_sample(7000);
+        assert_eq!(read_all(|b| mixer.read_samples(b)), mixed[7000..]);
+    }
+}
@@ -0,0 +1,651 @@
+mod effects;
+mod file;
+mod mixer;
+mod noise;
+mod osc;
+mod stream;
+mod tone;
+
+use songbird::input::{reader::MediaSource, Input};
+
+use crate::morse::{EncodeOptions, Fist, MorseChar, Timeline, Timing};
+
+pub use effects::{Chirp, Effects, Flutter, Qsb};
+pub use file::{AudioFile, FileFormat, SampleFormat};
+pub use mixer::{CWMixer, Station};
+pub use noise::{Noise, NoiseColor, NoiseConfig};
+pub use stream::TextFeed;
+pub use tone::{Envelope, Tone, Waveform};
+
+const NOISE_HEADROOM: f32 = 0.25; // -12 dB
+
+/*
+    fills the bytes with f32 samples from read, which returns how many it wrote
+    a misaligned buffer goes through a copy, so that it is never read short
+*/
+fn read_f32(buf: &mut [u8], read: impl FnOnce(&mut [f32]) -> usize) -> usize {
+    let (prefix, s, _) = unsafe { buf.align_to_mut::<f32>() };
+    if prefix.is_empty() {
+        return read(s) * 4;
+    }
+    let mut tmp = vec![0.0; buf.len() / 4];
+    let n = read(&mut tmp);
+    buf.chunks_exact_mut(4)
+        .zip(&tmp[..n])
+        .for_each(|(b, x)| b.copy_from_slice(&x.to_ne_bytes()));
+    n * 4
+}
+
+pub struct CWAudioPCM {
+    epos: usize,                // current position in the events
+    spos: usize,                // current position in a event
+    events: Vec<(usize, bool)>, // (length in samples, ON/OFF)
+    starts: Vec<usize>,         // start position of each event in samples
+    char_starts: Vec<usize>,    // index of the first event of each code
+
+    freq: f32,
+    srate: usize, // sample rate
+    tone: Tone,
+    osc: osc::Oscillator,
+    ramp: Vec<f32>, // fade in, precomputed from the tone
+    volume: f32,
+    pan: Option<f32>, // -1.0 (left) ~ 1.0 (right); None for mono
+    buf: Vec<f32>,    // mono samples before panning
+
+    modulator: Option<effects::Modulator>,
+    noise: Option<Noise>,
+
+    stream: Option<stream::Stream>, // events are generated while playing
+}
+
+impl CWAudioPCM {
+    pub fn new(str: String, wpm: f32, freq: f32, srate: usize) -> Self {
+        Self::from_codes(&crate::morse::get_morse_str(str), wpm, freq, srate)
+    }
+
+    pub fn from_codes(codes: &[MorseChar], wpm: f32, freq: f32, srate: usize) -> Self {
+        Self::with_timing(codes, &Timing::new(wpm), freq, srate)
+    }
+
+    pub fn with_timing(codes: &[MorseChar], timing: &Timing, freq: f32, srate: usize) -> Self {
+        Self::from_timeline(&Timeline::new(codes, timing), freq, srate)
+    }
+
+    pub fn from_timeline(timeline: &Timeline, freq: f32, srate: usize) -> Self {
+        let events = timeline.to_samples(srate);
+        let starts = events
+            .iter()
+            .scan(0, |pos, e| {
+                let s = *pos;
+                *pos += e.0;
+                Some(s)
+            })
+            .collect();
+
+        Self {
+            epos: 0,
+            spos: 0,
+            events,
+            starts,
+            char_starts: timeline.char_starts().to_vec(),
+
+            freq,
+            srate,
+            tone: Tone::default(),
+            osc: osc::Oscillator::new(Tone::default().waveform, freq, srate),
+            ramp: osc::ramp(&Tone::default(), srate),
+            volume: 1.0,
+            pan: None,
+            buf: Vec::new(),
+
+            modulator: None,
+            noise: None,
+
+            stream: None,
+        }
+    }
+
+    /*
+        generates events from the feed while playing, keeping only a few of them
+        not seekable
+    */
+    pub fn streaming(
+        feed: TextFeed,
+        timing: &Timing,
+        fist: &Fist,
+        opts: &EncodeOptions,
+        freq: f32,
+        srate: usize,
+    ) -> Self {
+        let mut pcm = Self::from_timeline(&Timeline::new(&[], timing), freq, srate);
+        pcm.events.clear();
+        pcm.starts.clear();
+        pcm.char_starts.clear();
+        pcm.stream = Some(stream::Stream::new(feed, timing, fist, opts, srate));
+        pcm
+    }
+
+    // replaces played events with the next ones from the stream
+    fn refill(&mut self) -> bool {
+        let Some(stream) = &mut self.stream else {
+            return false;
+        };
+        let Some(events) = stream.next_events() else {
+            return false;
+        };
+
+        let mut start = self.len_samples();
+        self.events.drain(..self.epos);
+        self.starts.drain(..self.epos);
+        self.epos = 0;
+        self.spos = 0;
+        for e in events {
+            self.events.push(e);
+            self.starts.push(start);
+            start += e.0;
+        }
+        true
+    }
+
+    pub fn with_tone(mut self, tone: &Tone) -> Self {
+        self.tone = tone.clone();
+        self.osc = osc::Oscillator::new(tone.waveform, self.freq, self.srate);
+        self.ramp = osc::ramp(tone, self.srate);
+        self
+    }
+
+    // capped at 1.0 so that the output stays within full scale
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume.clamp(0.0, 1.0);
+        self
+    }
+
+    // makes the output stereo
+    pub fn with_pan(mut self, pan: f32) -> Self {
+        self.pan = Some(pan.clamp(-1.0, 1.0));
+        self
+    }
+
+    // bytes per sample of all channels
+    fn frame_bytes(&self) -> u64 {
+        if self.pan.is_some() {
+            8
+        } else {
+            4
+        }
+    }
+
+    pub fn with_effects(mut self, effects: &Effects) -> Self {
+        self.modulator = (!effects.is_clean()).then(|| effects::Modulator::new(effects));
+        self
+    }
+
+    pub fn with_noise(mut self, config: &NoiseConfig) -> Self {
+        self.noise = Some(Noise::new(config, self.freq, self.srate));
+        self
+    }
+
+    pub fn get_duration(s: &str, wpm: f32) -> std::time::Duration {
+        Timeline::new(
+            &crate::morse::get_morse_str(s.to_string()),
+            &Timing::new(wpm),
+        )
+        .duration()
+    }
+
+    // length in samples
+    pub fn len_samples(&self) -> usize {
+        self.starts.last().unwrap_or(&0) + self.events.last().map(|e| e.0).unwrap_or(0)
+    }
+
+    // current position in samples
+    pub fn position(&self) -> usize {
+        self.starts
+            .get(self.epos)
+            .map(|s| s + self.spos)
+            .unwrap_or(self.len_samples())
+    }
+
+    pub fn seek_sample(&mut self, pos: usize) {
+        // the last event starting at or before pos
+        let i = self.starts.partition_point(|&s| s <= pos).saturating_sub(1);
+        if pos >= self.len_samples() {
+            self.epos = self.events.len();
+            self.spos = 0;
+        } else {
+            self.epos = i;
+            self.spos = pos - self.starts[i];
+        }
+    }
+
+    /*
+        seeks to the beginning of the n-th code (MorseChar) of the encoded text
+        not the n-th character of the text: spaces, wabun markers and dakuten are codes of their own
+    */
+    pub fn seek_code(&mut self, n: usize) -> std::io::Result<u64> {
+        let e = *self
+            .char_starts
+            .get(n)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "no such code"))?;
+        self.seek_sample(self.starts[e]);
+        Ok(self.starts[e] as u64 * self.frame_bytes())
+    }
+
+    // all the remaining samples, before panning; never returns for an open stream
+    pub fn render(&mut self) -> Vec<f32> {
+        let mut v = Vec::with_capacity(self.len_samples().saturating_sub(self.position()));
+        let mut buf = vec![0.0; 4096];
+        loop {
+            let n = self.read_samples(&mut buf);
+            if n == 0 {
+                break;
+            }
+            v.extend_from_slice(&buf[..n]);
+        }
+        v
+    }
+
+    // NOTE: songbird expects 48kHz; create with songbird::constants::SAMPLE_RATE_RAW
+    pub fn to_input(self) -> Input {
+        Input::float_pcm(
+            self.pan.is_some(),
+            songbird::input::reader::Reader::Extension(std::boxed::Box::new(self)),
+        )
+    }
+}
+
+impl MediaSource for CWAudioPCM {
+    fn is_seekable(&self) -> bool {
+        self.stream.is_none()
+    }
+    fn byte_len(&self) -> Option<u64> {
+        self.stream
+            .is_none()
+            .then(|| self.len_samples() as u64 * self.frame_bytes())
+    }
+}
+
+impl CWAudioPCM {
+    // fills out with samples, returns the number of samples written
+    pub fn read_samples(&mut self, out: &mut [f32]) -> usize {
+        let len = out.len();
+        let mut s = &mut *out;
+
+        // envelope length in samples
+        let env_len = self.ramp.len().saturating_sub(1);
+
+        while !s.is_empty() {
+            if self.epos >= self.events.len() && !self.refill() {
+                break;
+            }
+            let (length, on) = self.events[self.epos];
+            let t = length - self.spos;
+
+            let c = std::cmp::min(s.len(), t);
+            let of = s.len() <= c;
+
+            if on {
+                let spos = self.spos;
+                let start = self.starts[self.epos];
+                let srate = self.srate as f32;
+                let ramp = &self.ramp;
+                let osc = &self.osc;
+                // phase continues from the previous marks
+                let mut phase = osc.phase_at(start + spos);
+                s[..c].iter_mut().enumerate().for_each(|(i, x)| {
+                    let pos = spos + i;
+                    // Envelope gain (0.0~1.0)
+                    // the lower of fade in and fade out, so a mark shorter than two rises
+                    // still falls back to 0 instead of being cut off
+                    let mut gain = if ramp.is_empty() {
+                        1.0
+                    } else {
+                        ramp[pos.min(env_len)].min(ramp[(length - pos).min(env_len)])
+                    };
+                    let mut p = phase;
+                    if let Some(m) = &self.modulator {
+                        let t = (start + pos) as f32 / srate;
+                        gain *= m.gain(t);
+                        p = p
+                            .wrapping_add(osc::Oscillator::radians(m.phase(t, pos as f32 / srate)));
+                    }
+                    *x = gain * osc.sample(p);
+                    phase = phase.wrapping_add(osc.inc());
+                });
+            } else {
+                s[..c].iter_mut().for_each(|x| *x = 0.);
+            }
+            s = &mut s[c..];
+            self.spos += c;
+            if !of {
+                self.epos += 1;
+                self.spos = 0;
+            }
+        }
+        let n = len - s.len();
+
+        // room for the noise above the tone; crashes beyond it are limited softly
+        if let Some(noise) = &mut self.noise {
+            out[..n]
+                .iter_mut()
+                .for_each(|x| *x = ((*x + noise.sample()) * NOISE_HEADROOM).tanh());
+        }
+        if self.volume != 1.0 {
+            out[..n].iter_mut().for_each(|x| *x *= self.volume);
+        }
+
+        n
+    }
+}
+
+impl CWAudioPCM {
+    // samples in the output channels, interleaved when stereo; returns how many were written
+    fn read_frames(&mut self, s: &mut [f32]) -> usize {
+        let Some(pan) = self.pan else {
+            return self.read_samples(s);
+        };
+
+        // interleaved L/R, centre is as loud as mono
+        let mut mono = std::mem::take(&mut self.buf);
+        mono.resize(s.len() / 2, 0.0);
+        let n = self.read_samples(&mut mono);
+        let (l, r) = ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0));
+        s.chunks_exact_mut(2).zip(&mono[..n]).for_each(|(f, x)| {
+            f[0] = x * l;
+            f[1] = x * r;
+        });
+        self.buf = mono;
+        n * 2
+    }
+}
+
+impl std::io::Read for CWAudioPCM {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(read_f32(buf, |s| self.read_frames(s)))
+    }
+}
+
+impl std::io::Seek for CWAudioPCM {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        use std::io::SeekFrom;
+        if self.stream.is_some() {
+            return Err(std::io::ErrorKind::Unsupported.into());
+        }
+        let fb = self.frame_bytes();
+        let pos = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => (self.len_samples() as u64 * fb).checked_add_signed(d),
+            SeekFrom::Current(d) => (self.position() as u64 * fb).checked_add_signed(d),
+        }
+        .ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "seek before start")
+        })?;
+
+        // always at sample boundary
+        self.seek_sample((pos / fb) as usize);
+        Ok(self.position() as u64 * fb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{read_all_bytes, tone_power};
+    use std::io::Read;
+
+    #[test]
+    fn test_duration_matches_audio() {
+        let srate = songbird::constants::SAMPLE_RATE_RAW;
+        for (s, timing) in [
+            ("CQ DE JA1ABC", Timing::new(20.0)),
+            (" 5NN 1002M <AR>", Timing::new(37.0)),
+            ("テスト", Timing::farnsworth(18.0, 7.0)),
+        ] {
+            let timeline = Timeline::new(&crate::morse::get_morse_str(s.to_string()), &timing);
+            let samples = read_all_bytes(&mut CWAudioPCM::from_timeline(&timeline, 800.0, srate));
+            assert_eq!(samples.len(), timeline.len_samples(srate));
+
+            let d = timeline.duration().as_secs_f64() - samples.len() as f64 / srate as f64;
+            assert!(d.abs() < 1.0 / srate as f64);
+        }
+    }
+
+    #[test]
+    fn test_seek() {
+        use std::io::{Seek, SeekFrom};
+
+        let srate = 8000;
+        let mut pcm = CWAudioPCM::new("AB C".to_string(), 20.0, 600.0, srate);
+        let all = read_all_bytes(&mut pcm);
+        assert_eq!(pcm.byte_len(), Some(all.len() as u64 * 4));
+        assert!(read_all_bytes(&mut pcm).is_empty());
+
+        for p in [0, 1, 479, 480, 1234, all.len() - 1] {
+            assert_eq!(
+                pcm.seek(SeekFrom::Start(p as u64 * 4 + 2)).unwrap(),
+                p as u64 * 4
+            );
+            assert_eq!(read_all_bytes(&mut pcm), all[p..]);
+        }
+
+        pcm.seek(SeekFrom::End(-400)).unwrap();
+        assert_eq!(read_all_bytes(&mut pcm), all[all.len() - 100..]);
+        pcm.seek(SeekFrom::Start(400)).unwrap();
+        pcm.seek(SeekFrom::Current(-200)).unwrap();
+        assert_eq!(pcm.position(), 50);
+        assert!(pcm.seek(SeekFrom::Current(-400)).is_err());
+        assert_eq!(pcm.seek(SeekFrom::End(100)).unwrap(), all.len() as u64 * 4);
+        assert!(read_all_bytes(&mut pcm).is_empty());
+
+        // A, B, space, C
+        let c = pcm.seek_code(3).unwrap() as usize / 4;
+        let rest = read_all_bytes(&mut pcm);
+        assert_eq!(rest, all[c..]);
+        assert_eq!(mark_lengths(&rest).len(), 4);
+        assert_eq!(mark_lengths(&all[..c]).len(), 6);
+        assert!(pcm.seek_code(4).is_err());
+    }
+
+    #[test]
+    fn test_effects_seekable() {
+        use std::io::{Seek, SeekFrom};
+
+        let effects = Effects {
+            seed: Some(1),
+            ..("all".parse().unwrap())
+        };
+        let mut pcm = CWAudioPCM::new("TEST".to_string(), 20.0, 600.0, 8000).with_effects(&effects);
+        let all = read_all_bytes(&mut pcm);
+        let clean = read_all_bytes(&mut CWAudioPCM::new("TEST".to_string(), 20.0, 600.0, 8000));
+        assert_eq!(all.len(), clean.len());
+        assert_ne!(all, clean);
+
+        pcm.seek(SeekFrom::Start(2000 * 4)).unwrap();
+        assert_eq!(read_all_bytes(&mut pcm), all[2000..]);
+    }
+
+    #[test]
+    fn test_noise_headroom() {
+        let noise = NoiseConfig {
+            crashes: 5.0,
+            seed: Some(1),
+            ..NoiseConfig::new(10.0)
+        };
+        let v = CWAudioPCM::new("TEST TEST".to_string(), 20.0, 600.0, 8000)
+            .with_noise(&noise)
+            .render();
+        // crashes are rounded off, not cut flat
+        let peak = v.iter().fold(0f32, |a, x| a.max(x.abs()));
+        assert!(peak < 1.0, "{peak}");
+        assert!(v.iter().filter(|x| x.abs() > 0.95).count() < v.len() / 1000);
+    }
+
+    #[test]
+    fn test_read_misaligned() {
+        let whole = read_all_bytes(&mut CWAudioPCM::new("K".to_string(), 20.0, 600.0, 8000));
+
+        let mut pcm = CWAudioPCM::new("K".to_string(), 20.0, 600.0, 8000);
+        let mut v = vec![0u8; 4097];
+        let k = (0..4)
+            .find(|k| !(v.as_ptr() as usize + k).is_multiple_of(4))
+            .unwrap();
+        let buf = &mut v[k..k + 4092];
+        let mut read = Vec::new();
+        loop {
+            let n = pcm.read(buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            read.extend(
+                buf[..n]
+                    .chunks(4)
+                    .map(|b| f32::from_ne_bytes(b.try_into().unwrap())),
+            );
+        }
+        assert_eq!(read, whole);
+    }
+
+    #[test]
+    fn test_stereo() {
+        use std::io::{Seek, SeekFrom};
+
+        let mono = read_all_bytes(&mut CWAudioPCM::new("K".to_string(), 20.0, 600.0, 8000));
+        let mut pcm = CWAudioPCM::new("K".to_string(), 20.0, 600.0, 8000)
+            .with_volume(0.5)
+            .with_pan(-0.5);
+        let stereo = read_all_bytes(&mut pcm);
+        assert_eq!(stereo.len(), mono.len() * 2);
+        assert_eq!(pcm.byte_len(), Some(stereo.len() as u64 * 4));
+        for (f, x) in stereo.chunks(2).zip(&mono) {
+            assert!((f[0] - x * 0.5).abs() < 1e-6);
+            assert!((f[1] - x * 0.25).abs() < 1e-6);
+        }
+
+        assert_eq!(pcm.seek(SeekFrom::Start(1000 * 8 + 4)).unwrap(), 1000 * 8);
+        assert_eq!(read_all_bytes(&mut pcm), stereo[2000..]);
+    }
+
+    #[test]
+    fn test_volume_limit() {
+        // user and guild volumes multiplied over 1.0
+        for noise in [None, Some(NoiseConfig::new(10.0))] {
+            let mut pcm = CWAudioPCM::new("TEST".to_string(), 20.0, 600.0, 8000).with_volume(2.0);
+            if let Some(n) = &noise {
+                pcm = pcm.with_noise(n);
+            }
+            let peak = pcm.render().iter().fold(0f32, |a, x| a.max(x.abs()));
+            assert!(peak <= 1.0, "{peak}");
+        }
+    }
+
+    #[test]
+    fn test_short_mark_envelope() {
+        // a 30 ms dot at 40 wpm with a 50 ms rise never gets to full gain
+        let srate = 8000;
+        let tone = Tone {
+            rise_ms: 50.0,
+            ..Tone::default()
+        };
+        let samples = read_all_bytes(
+            &mut CWAudioPCM::new("E".to_string(), 40.0, 600.0, srate).with_tone(&tone),
+        );
+        let start = samples.iter().position(|&x| x != 0.0).unwrap();
+        let end = samples.iter().rposition(|&x| x != 0.0).unwrap() + 1;
+        assert!(
+            (end - start) as f32 <= 0.031 * srate as f32,
+            "{}",
+            end - start
+        );
+
+        // starts and ends at about 0, without a click
+        let peak = |v: &[f32]| v.iter().fold(0f32, |a, x| a.max(x.abs()));
+        let edge = srate / 1000; // 1 ms
+        assert!(peak(&samples[start..start + edge]) < 0.05);
+        assert!(peak(&samples[end - edge..end]) < 0.05);
+        assert!(peak(&samples[start..end]) > 0.1);
+    }
+
+    // lengths of non-silent runs, in samples
+    fn mark_lengths(samples: &[f32]) -> Vec<usize> {
+        let mut v = Vec::new();
+        let mut n = 0;
+        for &x in samples {
+            if x != 0.0 {
+                n += 1;
+            } else if n > 0 {
+                v.push(n);
+                n = 0;
+            }
+        }
+        v
+    }
+
+    #[test]
+    fn test_sample_rates() {
+        let timing = Timing::new(20.0);
+        let codes = crate::morse::get_morse_str("TEST".to_string());
+        for srate in [8000, 11025, 22050, 44100, 48000, 96000] {
+            let timeline = Timeline::new(&codes, &timing);
+            let samples = read_all_bytes(&mut CWAudioPCM::from_timeline(&timeline, 600.0, srate));
+
+            let secs = samples.len() as f64 / srate as f64;
+            assert!((secs - timeline.duration().as_secs_f64()).abs() < 1.0 / srate as f64);
+
+            // first sample of each mark is silent due to the envelope
+            let dot = 0.06 * srate as f64;
+            let marks = mark_lengths(&samples);
+            assert_eq!(marks.len(), 6, "srate: {}", srate);
+            for (m, units) in marks.iter().zip([3.0, 1.0, 1.0, 1.0, 1.0, 3.0]) {
+                assert!(
+                    (*m as f64 + 1.0 - dot * units).abs() <= 1.0,
+                    "srate: {}, {} != {}",
+                    srate,
+                    m,
+                    dot * units
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_phase_continuous() {
+        let (freq, srate) = (700.0, 8000);
+        let tone = Tone {
+            envelope: Envelope::Hard,
+            ..Tone::default()
+        };
+        let samples = read_all_bytes(
+            &mut CWAudioPCM::new("PARIS".to_string(), 25.0, freq, srate).with_tone(&tone),
+        );
+
+        // every mark is cut from the same continuous sine
+        let mut marks = 0;
+        for (pos, &x) in samples.iter().enumerate() {
+            if x != 0.0 {
+                marks += 1;
+                let expected =
+                    (2.0 * std::f64::consts::PI * freq as f64 * pos as f64 / srate as f64).sin();
+                assert!((x as f64 - expected).abs() < 1e-3, "{pos}");
+            }
+        }
+        assert!(marks > srate);
+    }
+
+    #[test]
+    fn test_spectrum() {
+        let (freq, srate) = (600.0, 16000);
+        let samples = read_all_bytes(&mut CWAudioPCM::new(
+            "PARIS PARIS".to_string(),
+            30.0,
+            freq,
+            srate,
+        ));
+
+        // no clicks: the energy stays close to the carrier
+        let carrier = tone_power(&samples, freq, srate);
+        for f in [1313.0, 1777.0, 2345.0, 3131.0, 4567.0] {
+            let db = 10.0 * (tone_power(&samples, f, srate) / carrier).log10();
+            assert!(db < -90.0, "{f} Hz: {db} dB");
+        }
+    }
+}
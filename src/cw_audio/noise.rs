@@ -0,0 +1,250 @@
+use rand::{rngs::StdRng, Rng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoiseColor {
+    White,
+    #[default]
+    Pink,
+}
+
+impl NoiseColor {
+    pub const ALL: [NoiseColor; 2] = [NoiseColor::White, NoiseColor::Pink];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoiseColor::White => "white",
+            NoiseColor::Pink => "pink",
+        }
+    }
+}
+
+crate::util::impl_named!(NoiseColor, "noise color");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseConfig {
+    // ratio of the tone power to the background noise power, in dB
+    pub snr_db: f32,
+    pub color: NoiseColor,
+    // average number of static crashes per second
+    pub crashes: f32,
+    // receiver bandpass width in Hz, centered on the tone; None for no filter
+    pub bandwidth: Option<f32>,
+    pub seed: Option<u64>,
+}
+
+impl NoiseConfig {
+    pub fn new(snr_db: f32) -> Self {
+        Self {
+            snr_db,
+            color: NoiseColor::Pink,
+            crashes: 0.2,
+            bandwidth: Some(500.0),
+            seed: None,
+        }
+    }
+}
+
+// Paul Kellet's refined method, from white noise
+#[derive(Default)]
+struct Pink {
+    p: [f32; 7],
+}
+
+impl Pink {
+    fn process(&mut self, w: f32) -> f32 {
+        let p = &mut self.p;
+        p[0] = 0.99886 * p[0] + w * 0.0555179;
+        p[1] = 0.99332 * p[1] + w * 0.0750759;
+        p[2] = 0.96900 * p[2] + w * 0.153852;
+        p[3] = 0.86650 * p[3] + w * 0.3104856;
+        p[4] = 0.55000 * p[4] + w * 0.5329522;
+        p[5] = -0.7616 * p[5] - w * 0.0168980;
+        let y = p.iter().sum::<f32>() + w * 0.5362;
+        p[6] = w * 0.115926;
+        y * 0.2
+    }
+}
+
+const IMPULSE_LEN: usize = 1 << 15; // long enough for the slowest pole to die out
+
+// RBJ bandpass biquad, 0 dB peak gain
+struct Bandpass {
+    b: [f32; 3],
+    a: [f32; 2],
+    x: [f32; 2],
+    y: [f32; 2],
+}
+
+impl Bandpass {
+    fn new(freq: f32, bandwidth: f32, srate: usize) -> Self {
+        let w0 = 2.0 * std::f32::consts::PI * freq / srate as f32;
+        let q = (freq / bandwidth).max(0.1);
+        let alpha = w0.sin() / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        Self {
+            b: [alpha / a0, 0.0, -alpha / a0],
+            a: [-2.0 * w0.cos() / a0, (1.0 - alpha) / a0],
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+/// background noise and static crashes, to be added to a tone of amplitude 1
+pub struct Noise {
+    rng: StdRng,
+    color: NoiseColor,
+    pink: Pink,
+    crash_prob: f32, // per sample
+    crash_amp: f32,
+    crash_decay: f32,
+    crash_scale: f32,
+    filter: Option<Bandpass>,
+    gain: f32,
+    srate: usize,
+}
+
+impl Noise {
+    pub fn new(config: &NoiseConfig, freq: f32, srate: usize) -> Self {
+        let filter = || config.bandwidth.map(|bw| Bandpass::new(freq, bw, srate));
+
+        /*
+            white noise of variance 1 through linear filters has the power of their impulse response
+            the background is set against a sine of amplitude 1 (power 0.5)
+        */
+        let impulse_power = |color: NoiseColor| {
+            let (mut pink, mut bp) = (Pink::default(), filter());
+            (0..IMPULSE_LEN)
+                .map(|i| {
+                    let x = if i == 0 { 1.0 } else { 0.0 };
+                    let x = match color {
+                        NoiseColor::White => x,
+                        NoiseColor::Pink => pink.process(x),
+                    };
+                    let y = bp.as_mut().map(|f| f.process(x)).unwrap_or(x);
+                    (y * y) as f64
+                })
+                .sum::<f64>() as f32
+        };
+        let power = impulse_power(config.color).max(f32::EPSILON);
+        let gain = (0.5 / 10f32.powf(config.snr_db / 10.0) / power).sqrt();
+
+        Self {
+            rng: crate::util::rng(config.seed),
+            color: config.color,
+            pink: Pink::default(),
+            crash_prob: config.crashes / srate as f32,
+            crash_amp: 0.0,
+            crash_decay: 0.0,
+            // crashes are white; measured in tone amplitudes after the filter
+            crash_scale: 1.0 / (gain * impulse_power(NoiseColor::White).max(f32::EPSILON).sqrt()),
+            filter: filter(),
+            gain,
+            srate,
+        }
+    }
+
+    pub fn sample(&mut self) -> f32 {
+        let w = crate::util::gauss(&mut self.rng);
+        let mut x = match self.color {
+            NoiseColor::White => w,
+            NoiseColor::Pink => self.pink.process(w),
+        };
+
+        // static crash: sudden burst up to a few times the tone, decaying in 10~100 ms
+        if self.crash_prob > 0.0 && self.rng.gen::<f32>() < self.crash_prob {
+            self.crash_amp = self.rng.gen_range(0.3..1.5);
+            let secs: f32 = self.rng.gen_range(0.01..0.1);
+            self.crash_decay = (-1.0 / (secs * self.srate as f32)).exp();
+        }
+        if self.crash_amp > 0.01 {
+            x += self.crash_amp * self.crash_scale * crate::util::gauss(&mut self.rng);
+            self.crash_amp *= self.crash_decay;
+        }
+
+        let x = match &mut self.filter {
+            Some(f) => f.process(x),
+            None => x,
+        };
+        x * self.gain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::tone_power;
+
+    fn power(v: &[f32]) -> f32 {
+        v.iter().map(|x| x * x).sum::<f32>() / v.len() as f32
+    }
+
+    #[test]
+    fn test_noise_snr() {
+        let srate = 8000;
+        for color in [NoiseColor::White, NoiseColor::Pink] {
+            for (snr, bw) in [(0.0, None), (10.0, Some(300.0)), (-6.0, Some(100.0))] {
+                let mut n = Noise::new(
+                    &NoiseConfig {
+                        color,
+                        crashes: 0.0,
+                        bandwidth: bw,
+                        seed: Some(1),
+                        ..NoiseConfig::new(snr)
+                    },
+                    700.0,
+                    srate,
+                );
+                let v = (0..srate * 8).map(|_| n.sample()).collect::<Vec<_>>();
+                let measured = 10.0 * (0.5 / power(&v)).log10();
+                assert!((measured - snr).abs() < 1.0, "{color:?} {snr} {measured}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_noise_bandpass() {
+        let srate = 8000;
+        let mut n = Noise::new(
+            &NoiseConfig {
+                color: NoiseColor::White,
+                crashes: 0.0,
+                bandwidth: Some(200.0),
+                seed: Some(2),
+                ..NoiseConfig::new(0.0)
+            },
+            700.0,
+            srate,
+        );
+        let v = (0..srate * 4).map(|_| n.sample()).collect::<Vec<_>>();
+        let near = tone_power(&v, 700.0, srate);
+        let far = tone_power(&v, 2000.0, srate);
+        assert!(near > far * 10.0, "{near} {far}");
+    }
+
+    #[test]
+    fn test_noise_crashes() {
+        let srate = 8000;
+        let config = NoiseConfig {
+            crashes: 2.0,
+            bandwidth: None,
+            seed: Some(3),
+            ..NoiseConfig::new(20.0)
+        };
+        let mut n = Noise::new(&config, 700.0, srate);
+        let v = (0..srate * 5).map(|_| n.sample()).collect::<Vec<_>>();
+        let peak = v.iter().fold(0f32, |a, x| a.max(x.abs()));
+        // background is 20 dB below the tone, crashes are far above it
+        assert!(peak > 1.0, "{peak}");
+        assert!(power(&v) > 0.5 / 100.0 * 2.0);
+    }
+}
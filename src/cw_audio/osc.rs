@@ -0,0 +1,106 @@
+use std::sync::OnceLock;
+
+use super::{Envelope, Tone, Waveform};
+
+const TABLE_BITS: u32 = 12;
+const TABLE_LEN: usize = 1 << TABLE_BITS;
+const FRAC_BITS: u32 = 32 - TABLE_BITS;
+
+// one cycle of each waveform, with a guard point for interpolation
+fn table(waveform: Waveform) -> &'static [f32] {
+    static TABLES: OnceLock<Vec<Vec<f32>>> = OnceLock::new();
+    let tables = TABLES.get_or_init(|| {
+        Waveform::ALL
+            .iter()
+            .map(|w| {
+                (0..=TABLE_LEN)
+                    .map(|i| w.sample(2.0 * std::f32::consts::PI * i as f32 / TABLE_LEN as f32))
+                    .collect()
+            })
+            .collect()
+    });
+    let i = Waveform::ALL
+        .iter()
+        .position(|w| *w == waveform)
+        .unwrap_or(0);
+    &tables[i]
+}
+
+/*
+    table lookup oscillator with a 32-bit phase accumulator (2^32 = one cycle)
+    phase is a function of the absolute sample position, so that marks are cut
+    from one continuous tone even after seeking
+*/
+pub(super) struct Oscillator {
+    table: &'static [f32],
+    inc: u32, // per sample
+}
+
+impl Oscillator {
+    pub fn new(waveform: Waveform, freq: f32, srate: usize) -> Self {
+        let cycles = (freq as f64 / srate as f64).rem_euclid(1.0);
+        Self {
+            table: table(waveform),
+            inc: (cycles * 2f64.powi(32)).round() as u64 as u32,
+        }
+    }
+
+    pub fn inc(&self) -> u32 {
+        self.inc
+    }
+
+    pub fn phase_at(&self, pos: usize) -> u32 {
+        (pos as u32).wrapping_mul(self.inc)
+    }
+
+    pub fn radians(r: f32) -> u32 {
+        (r as f64 / (2.0 * std::f64::consts::PI) * 2f64.powi(32)).rem_euclid(2f64.powi(32)) as u32
+    }
+
+    pub fn sample(&self, phase: u32) -> f32 {
+        let i = (phase >> FRAC_BITS) as usize;
+        let frac = (phase & ((1 << FRAC_BITS) - 1)) as f32 / (1u32 << FRAC_BITS) as f32;
+        let (a, b) = (self.table[i], self.table[i + 1]);
+        a + (b - a) * frac
+    }
+}
+
+// rising ramp, gain at each sample from the key-down; empty for hard keying
+pub(super) fn ramp(tone: &Tone, srate: usize) -> Vec<f32> {
+    let len = ((tone.rise_ms / 1000.0) * srate as f32) as usize;
+    if tone.envelope == Envelope::Hard || len == 0 {
+        return Vec::new();
+    }
+    (0..=len)
+        .map(|i| tone.envelope.gain(i as f32 / len as f32))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oscillator() {
+        let srate = 48000;
+        let freq = 777.7;
+        let osc = Oscillator::new(Waveform::Sine, freq, srate);
+
+        // accumulating equals computing from the position
+        let mut phase = osc.phase_at(0);
+        for pos in 0..200000 {
+            assert_eq!(phase, osc.phase_at(pos));
+            let exact =
+                (2.0 * std::f64::consts::PI * freq as f64 * pos as f64 / srate as f64).sin();
+            assert!((osc.sample(phase) as f64 - exact).abs() < 1e-4, "{pos}");
+            phase = phase.wrapping_add(osc.inc());
+        }
+
+        let near = |a: u32, b: u32| a.wrapping_sub(b).min(b.wrapping_sub(a)) < 1 << 8;
+        assert!(near(Oscillator::radians(std::f32::consts::PI), 1 << 31));
+        assert!(near(
+            Oscillator::radians(-std::f32::consts::PI / 2.0),
+            3 << 30
+        ));
+    }
+}
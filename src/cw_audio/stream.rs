@@ -0,0 +1,260 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::morse::{EncodeOptions, Encoder, Fist, Keyer, MorseChar, Timing};
+
+const CHUNK_CHARS: usize = 256; // encoded at once
+
+#[derive(Default)]
+struct FeedState {
+    text: String,
+    closed: bool,
+}
+
+/// text to be sent by a streaming CWAudioPCM; can be appended while playing
+#[derive(Clone, Default)]
+pub struct TextFeed {
+    inner: Arc<Mutex<FeedState>>,
+}
+
+impl TextFeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, s: &str) {
+        if let Ok(mut st) = self.inner.lock() {
+            st.text.push_str(s);
+        }
+    }
+
+    // the stream ends after the text pushed so far
+    pub fn close(&self) {
+        if let Ok(mut st) = self.inner.lock() {
+            st.closed = true;
+        }
+    }
+
+    /*
+        takes up to max chars, cut at a space so that no prosign, digraph or dakuten is split
+        a longer run without spaces is taken up to the next space or the end of the text
+        empty while waiting for text, None when closed and all taken
+    */
+    fn take(&self, max: usize) -> Option<String> {
+        let mut st = self.inner.lock().ok()?;
+        if st.text.is_empty() {
+            return (!st.closed).then(String::new);
+        }
+
+        let end = match st.text.char_indices().nth(max) {
+            None => st.text.len(),
+            Some((i, _)) => st.text[..i]
+                .rfind(char::is_whitespace)
+                .or_else(|| st.text[i..].find(char::is_whitespace).map(|j| i + j))
+                .and_then(|j| st.text[j..].chars().next().map(|c| j + c.len_utf8()))
+                .unwrap_or(st.text.len()),
+        };
+        Some(st.text.drain(..end).collect())
+    }
+}
+
+// generates events on demand from the feed
+pub(super) struct Stream {
+    feed: TextFeed,
+    keyer: Keyer,
+    encoder: Encoder,
+    pending: VecDeque<MorseChar>,
+    started: bool,
+    finished: bool,
+    t: f64,     // end of the events so far, in seconds
+    pos: usize, // same in samples
+    srate: usize,
+}
+
+impl Stream {
+    pub fn new(
+        feed: TextFeed,
+        timing: &Timing,
+        fist: &Fist,
+        opts: &EncodeOptions,
+        srate: usize,
+    ) -> Self {
+        Self {
+            feed,
+            keyer: Keyer::new(timing, fist),
+            encoder: Encoder::new(opts),
+            pending: VecDeque::new(),
+            started: false,
+            finished: false,
+            t: 0.0,
+            pos: 0,
+            srate,
+        }
+    }
+
+    // same rounding as Timeline::to_samples
+    fn samples_of(&mut self, events: impl IntoIterator<Item = (f64, bool)>) -> Vec<(usize, bool)> {
+        events
+            .into_iter()
+            .map(|(l, on)| {
+                self.t += l;
+                let end = (self.t * self.srate as f64).round() as usize;
+                let len = end - self.pos;
+                self.pos = end;
+                (len, on)
+            })
+            .collect()
+    }
+
+    // events of the next character; silence while waiting for text, None at the end
+    pub fn next_events(&mut self) -> Option<Vec<(usize, bool)>> {
+        if !self.started {
+            self.started = true;
+            let lead = self.keyer.lead();
+            return Some(self.samples_of(lead));
+        }
+
+        loop {
+            if let Some(c) = self.pending.pop_front() {
+                // spaces are merged across chunks
+                if c.is_space() && self.keyer.last().is_none_or(|l| l.is_space()) {
+                    continue;
+                }
+                let events = self
+                    .keyer
+                    .gap_before(c)
+                    .into_iter()
+                    .chain(self.keyer.key(c))
+                    .collect::<Vec<_>>();
+                return Some(self.samples_of(events));
+            }
+
+            match self.feed.take(CHUNK_CHARS) {
+                Some(s) if s.is_empty() => {
+                    // waiting for text; a short pause, keeps songbird playing
+                    let idle = self.keyer.lead();
+                    return Some(self.samples_of(idle));
+                }
+                Some(s) => self.pending.extend(self.encoder.encode(&s)),
+                None if !self.finished => {
+                    self.finished = true;
+                    let last = self.keyer.finish();
+                    return Some(self.samples_of(last));
+                }
+                None => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::CWAudioPCM;
+    use super::*;
+    use crate::test_util::read_all;
+    use songbird::input::reader::MediaSource;
+
+    #[test]
+    fn test_stream_matches_whole() {
+        let s = "CQ CQ DE JA1ABC <AR>";
+        let feed = TextFeed::new();
+        feed.push(s);
+        feed.close();
+        let timing = Timing::new(25.0);
+        let mut streamed = CWAudioPCM::streaming(
+            feed,
+            &timing,
+            &Fist::default(),
+            &EncodeOptions::default(),
+            700.0,
+            8000,
+        );
+        let mut whole = CWAudioPCM::new(s.to_string(), 25.0, 700.0, 8000);
+        assert!(!streamed.is_seekable());
+        assert_eq!(
+            read_all(|b| streamed.read_samples(b)),
+            read_all(|b| whole.read_samples(b))
+        );
+    }
+
+    #[test]
+    fn test_stream_chunk_boundary() {
+        // the cut falls at the end of a wabun run, inside one and in a run of half-width
+        // kana with dakuten (ｶﾞ), which has no space to cut at
+        let texts = [
+            "イロハ ".repeat(64) + "DE JA1ABC K",
+            "CQ ".to_string() + &"イロハ ".repeat(100) + "<AR>",
+            "ｶﾞ".repeat(200) + " K",
+        ];
+        for text in texts {
+            let feed = TextFeed::new();
+            feed.push(&text);
+            feed.close();
+            let mut encoder = Encoder::new(&EncodeOptions::default());
+            let mut chunks = 0;
+            let mut codes = Vec::new();
+            while let Some(s) = feed.take(CHUNK_CHARS) {
+                codes.extend(encoder.encode(&s));
+                chunks += 1;
+            }
+            assert!(chunks > 1);
+            assert_eq!(codes, crate::morse::get_morse_str(text.clone()), "{text}");
+        }
+
+        let s = "イロハ ".repeat(64) + "DE JA1ABC K";
+        let feed = TextFeed::new();
+        feed.push(&s);
+        feed.close();
+        let timing = Timing::new(40.0);
+        let mut streamed = CWAudioPCM::streaming(
+            feed,
+            &timing,
+            &Fist::default(),
+            &EncodeOptions::default(),
+            700.0,
+            8000,
+        );
+        let mut whole = CWAudioPCM::new(s, 40.0, 700.0, 8000);
+        assert_eq!(
+            read_all(|b| streamed.read_samples(b)),
+            read_all(|b| whole.read_samples(b))
+        );
+    }
+
+    #[test]
+    fn test_stream_append() {
+        let feed = TextFeed::new();
+        let mut pcm = CWAudioPCM::streaming(
+            feed.clone(),
+            &Timing::new(30.0),
+            &Fist::default(),
+            &EncodeOptions::default(),
+            700.0,
+            8000,
+        );
+
+        // keeps producing silence until text comes
+        let mut buf = vec![1.0; 8000];
+        let mut n = 0;
+        while n < buf.len() {
+            n += pcm.read_samples(&mut buf[n..]);
+        }
+        assert!(buf.iter().all(|&x| x == 0.0));
+
+        // long text is generated little by little
+        feed.push(&"PARIS ".repeat(1000));
+        feed.push("END");
+        feed.close();
+        let mut total = 0;
+        loop {
+            let n = pcm.read_samples(&mut buf);
+            if n == 0 {
+                break;
+            }
+            total += n;
+            assert!(pcm.events.len() < 100);
+        }
+        // 1000 words at 30 wpm
+        assert!(total > 8000 * 1000 * 2);
+    }
+}
@@ -0,0 +1,139 @@
+use std::f32::consts::PI;
+
+// shape of the rise and fall of each mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Envelope {
+    #[default]
+    RaisedCosine,
+    BlackmanHarris,
+    Linear,
+    Hard, // no ramp, clicks
+}
+
+impl Envelope {
+    pub const ALL: [Envelope; 4] = [
+        Envelope::RaisedCosine,
+        Envelope::BlackmanHarris,
+        Envelope::Linear,
+        Envelope::Hard,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Envelope::RaisedCosine => "cosine",
+            Envelope::BlackmanHarris => "blackman-harris",
+            Envelope::Linear => "linear",
+            Envelope::Hard => "hard",
+        }
+    }
+
+    // gain at x (0.0 ~ 1.0) of the ramp
+    pub fn gain(&self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            Envelope::RaisedCosine => 0.5 * (1.0 - (PI * x).cos()),
+            // rising half of the window
+            Envelope::BlackmanHarris => {
+                0.35875 - 0.48829 * (PI * x).cos() + 0.14128 * (2.0 * PI * x).cos()
+                    - 0.01168 * (3.0 * PI * x).cos()
+            }
+            Envelope::Linear => x,
+            Envelope::Hard => 1.0,
+        }
+    }
+}
+
+// timbre of the tone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    SoftSquare,
+    Buzzy, // rich in harmonics, like an old rig
+}
+
+const SOFT_SQUARE_DRIVE: f32 = 3.0;
+const BUZZY_HARMONICS: [f32; 6] = [1.0, 0.5, 0.33, 0.25, 0.2, 0.17];
+
+impl Waveform {
+    pub const ALL: [Waveform; 3] = [Waveform::Sine, Waveform::SoftSquare, Waveform::Buzzy];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Waveform::Sine => "sine",
+            Waveform::SoftSquare => "soft-square",
+            Waveform::Buzzy => "buzzy",
+        }
+    }
+
+    // -1.0 ~ 1.0 at the phase in radians
+    pub fn sample(&self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => phase.sin(),
+            Waveform::SoftSquare => {
+                (SOFT_SQUARE_DRIVE * phase.sin()).tanh() / SOFT_SQUARE_DRIVE.tanh()
+            }
+            Waveform::Buzzy => {
+                BUZZY_HARMONICS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, a)| a * ((i + 1) as f32 * phase).sin())
+                    .sum::<f32>()
+                    * 0.6
+            }
+        }
+    }
+}
+
+crate::util::impl_named!(Envelope, "envelope");
+
+crate::util::impl_named!(Waveform, "waveform");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tone {
+    pub rise_ms: f32, // length of the ramp
+    pub envelope: Envelope,
+    pub waveform: Waveform,
+}
+
+impl Default for Tone {
+    fn default() -> Self {
+        Self {
+            rise_ms: 10.0,
+            envelope: Envelope::RaisedCosine,
+            waveform: Waveform::Sine,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope() {
+        for e in Envelope::ALL {
+            assert_eq!(e.name().parse::<Envelope>().unwrap(), e);
+            let g = (0..=100)
+                .map(|i| e.gain(i as f32 / 100.0))
+                .collect::<Vec<_>>();
+            assert!(g.windows(2).all(|w| w[0] <= w[1] + 1e-6), "{e}");
+            assert!((g[100] - 1.0).abs() < 1e-4, "{e}");
+            assert!(g[0] < 1e-4 || e == Envelope::Hard, "{e}");
+        }
+    }
+
+    #[test]
+    fn test_waveform() {
+        for w in Waveform::ALL {
+            assert_eq!(w.name().parse::<Waveform>().unwrap(), w);
+            let v = (0..1000)
+                .map(|i| w.sample(i as f32 / 1000.0 * 2.0 * PI))
+                .collect::<Vec<_>>();
+            let peak = v.iter().fold(0f32, |a, x| a.max(x.abs()));
+            assert!(peak <= 1.0 && peak > 0.9, "{w} {peak}");
+            assert!(v.iter().sum::<f32>().abs() < 1e-2, "{w}");
+        }
+        assert!("triangle".parse::<Waveform>().is_err());
+    }
+}
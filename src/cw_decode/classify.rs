@@ -0,0 +1,416 @@
+use crate::morse::{Code, Element, MorseChar};
+
+// level followers, in seconds
+const HI_ATTACK: f32 = 0.02;
+const HI_FOLLOW: f32 = 0.05; // during marks
+const HI_DECAY: f32 = 2.0; // during gaps
+const LO_FOLLOW: f32 = 0.2; // during gaps
+const LO_RISE: f32 = 5.0; // toward levels more like a mark
+const SNR_GATE: f32 = 3.0; // signal level over the noise level, in amplitude
+const MIN_LEVEL: f32 = 1e-4;
+const BLANK_RATIO: f32 = 0.5; // beside the tone over the tone
+const BLANK_LEVEL: f32 = 3.0; // beside the tone over the noise level
+const BLANK_HOLD: f32 = 0.02; // seconds, as the noise comes and goes
+
+/*
+    on/off decision from the tone magnitude
+    the signal level is followed during marks and the noise level during gaps,
+    and the threshold sits between them with hysteresis
+*/
+pub(super) struct Slicer {
+    hi: f32,
+    lo: f32,
+    on: bool,
+    started: bool,
+    blank: f32, // time left
+    dt: f32,
+}
+
+impl Slicer {
+    pub fn new(dt: f32) -> Self {
+        Self {
+            hi: 0.0,
+            lo: 0.0,
+            on: false,
+            started: false,
+            blank: 0.0,
+            dt,
+        }
+    }
+
+    /*
+        side is the magnitude beside the tone
+        while broadband noise like a static crash is about as strong as the tone,
+        its power is taken out of the tone and the levels are kept
+    */
+    pub fn push(&mut self, e: f32, side: f32) -> bool {
+        if self.started && side > e * BLANK_RATIO && side > self.lo * BLANK_LEVEL {
+            self.blank = BLANK_HOLD;
+        }
+        if self.blank > 0.0 {
+            self.blank -= self.dt;
+            return self.decide((e * e - side * side).max(0.0).sqrt());
+        }
+        if !self.started {
+            self.started = true;
+            self.hi = e;
+            self.lo = e;
+        }
+        let dt = self.dt;
+        let follow = |level: &mut f32, target: f32, tc: f32| {
+            *level += (target - *level) * (dt / tc).min(1.0)
+        };
+        if e > self.hi {
+            follow(&mut self.hi, e, HI_ATTACK);
+        } else if self.on {
+            follow(&mut self.hi, e, HI_FOLLOW);
+        } else {
+            follow(&mut self.hi, self.lo, HI_DECAY);
+        }
+        // a mark missed by the threshold must not pull the noise level up to it
+        if !self.on {
+            let tc = if e < (self.hi + self.lo) / 2.0 {
+                LO_FOLLOW
+            } else {
+                LO_RISE
+            };
+            follow(&mut self.lo, e, tc);
+        }
+
+        self.decide(e)
+    }
+
+    fn decide(&mut self, e: f32) -> bool {
+        let present = self.hi > self.lo * SNR_GATE && self.hi > MIN_LEVEL;
+        let th = if self.on { 0.4 } else { 0.6 };
+        self.on = present && e > self.lo + (self.hi - self.lo) * th;
+        self.on
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Run {
+    Mark(f64), // in seconds
+    Gap(f64),  // between two marks
+}
+
+/*
+    merges on/off steps into marks and gaps
+    blips shorter than min are a part of the gap, dropouts shorter than min a part of the mark
+*/
+#[derive(Default)]
+pub(super) struct Runs {
+    on: bool,
+    run: f64,
+    gap: f64,
+    gap_at_start: f64,
+    pending: Option<f64>, // mark which may still continue after a dropout
+    seen: bool,           // any mark so far
+}
+
+impl Runs {
+    pub fn step(&mut self, on: bool, dt: f64, min: f64, out: &mut Vec<Run>) {
+        if on {
+            if !self.on {
+                self.on = true;
+                self.run = 0.0;
+                self.gap_at_start = self.gap;
+            }
+            self.run += dt;
+            return;
+        }
+
+        if self.on {
+            self.end_mark(min, out);
+        }
+        self.gap += dt;
+        if self.gap >= min {
+            if let Some(p) = self.pending.take() {
+                out.push(Run::Mark(p));
+            }
+        }
+    }
+
+    fn end_mark(&mut self, min: f64, out: &mut Vec<Run>) {
+        self.on = false;
+        match self.pending {
+            Some(p) if self.gap_at_start < min => {
+                self.pending = Some(p + self.gap_at_start + self.run);
+                self.gap = 0.0;
+            }
+            _ if self.run < min => self.gap = self.gap_at_start + self.run,
+            _ => {
+                if self.seen {
+                    out.push(Run::Gap(self.gap_at_start));
+                }
+                self.seen = true;
+                self.pending = Some(self.run);
+                self.gap = 0.0;
+            }
+        }
+    }
+
+    // length of the gap so far, after the last mark
+    pub fn gap(&self) -> Option<f64> {
+        (!self.on && self.pending.is_none() && self.seen).then_some(self.gap)
+    }
+
+    pub fn finish(&mut self, min: f64, out: &mut Vec<Run>) {
+        if self.on {
+            self.end_mark(min, out);
+        }
+        if let Some(p) = self.pending.take() {
+            out.push(Run::Mark(p));
+        }
+    }
+}
+
+const BOOTSTRAP_MARKS: usize = 8;
+const BOOTSTRAP_PAUSE: f64 = 1.5; // seconds
+const ADAPT: f64 = 0.2;
+
+/*
+    classifies marks and gaps into characters, following the speed
+    runs are kept until the speed can be estimated from them
+*/
+pub(super) struct Classifier {
+    dot: Option<f64>,
+    char_gap: f64,
+    history: Vec<Run>,
+    code: Option<Code>,
+    flushed: u8, // 1 when the character is emitted during the gap, 2 when the space is
+    after_space: bool,
+    elements: Vec<(Element, f64)>, // as classified, with the length
+}
+
+impl Classifier {
+    pub fn new() -> Self {
+        Self {
+            dot: None,
+            char_gap: 0.0,
+            history: Vec::new(),
+            code: None,
+            flushed: 0,
+            after_space: true,
+            elements: Vec::new(),
+        }
+    }
+
+    pub fn dot(&self) -> Option<f64> {
+        self.dot
+    }
+
+    // shortest mark or gap taken as such
+    pub fn min_run(&self) -> f64 {
+        self.dot.map(|d| d * 0.3).unwrap_or(0.01)
+    }
+
+    fn word_gap(&self, dot: f64) -> f64 {
+        (5.0 * dot).max(self.char_gap * 5.0 / 3.0)
+    }
+
+    pub fn push(&mut self, run: Run, out: &mut Vec<MorseChar>) {
+        let Some(dot) = self.dot else {
+            self.history.push(run);
+            if self
+                .history
+                .iter()
+                .filter(|r| matches!(r, Run::Mark(_)))
+                .count()
+                >= BOOTSTRAP_MARKS
+            {
+                self.bootstrap(out);
+            }
+            return;
+        };
+
+        match run {
+            Run::Mark(m) => {
+                let dash = m > 2.0 * dot;
+                let unit = if dash { m / 3.0 } else { m };
+                let e = if dash { Element::Dash } else { Element::Dot };
+                self.elements.push((e, m));
+                self.dot = Some(dot + (unit - dot) * ADAPT);
+
+                let c = self.code.unwrap_or(Code::new(0, 0));
+                if c.len() < 16 {
+                    self.code = c.concat(Code::new(1, dash as u16));
+                }
+                self.flushed = 0;
+            }
+            Run::Gap(g) => {
+                self.gap(g, out);
+                // marks look shorter and gaps longer with slow rise, so both are followed
+                let e = if g < 2.0 * dot {
+                    self.dot = Some(dot + (g - dot) * ADAPT);
+                    Element::ElementGap
+                } else if g < self.word_gap(dot) {
+                    self.char_gap += (g - self.char_gap) * ADAPT;
+                    Element::CharGap
+                } else {
+                    Element::WordGap
+                };
+                self.elements.push((e, g));
+            }
+        }
+    }
+
+    // emits the character and the space as the gap grows
+    pub fn gap(&mut self, g: f64, out: &mut Vec<MorseChar>) {
+        let Some(dot) = self.dot else {
+            if g > BOOTSTRAP_PAUSE && !self.history.is_empty() {
+                self.bootstrap(out);
+            }
+            return;
+        };
+
+        if g >= 2.0 * dot && self.flushed < 1 {
+            self.flushed = 1;
+            if let Some(c) = self.code.take() {
+                out.push(MorseChar::Char(c));
+                self.after_space = false;
+            }
+        }
+        if g >= self.word_gap(dot) && self.flushed < 2 {
+            self.flushed = 2;
+            if !self.after_space {
+                out.push(MorseChar::Space);
+                self.after_space = true;
+            }
+        }
+    }
+
+    pub fn take_elements(&mut self) -> Vec<(Element, f64)> {
+        std::mem::take(&mut self.elements)
+    }
+
+    pub fn finish(&mut self, out: &mut Vec<MorseChar>) {
+        if self.dot.is_none() && !self.history.is_empty() {
+            self.bootstrap(out);
+        }
+        if let Some(c) = self.code.take() {
+            out.push(MorseChar::Char(c));
+        }
+    }
+
+    fn bootstrap(&mut self, out: &mut Vec<MorseChar>) {
+        let history = std::mem::take(&mut self.history);
+        let marks = history
+            .iter()
+            .filter_map(|r| match r {
+                Run::Mark(m) => Some(*m),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let gaps = history
+            .iter()
+            .filter_map(|r| match r {
+                Run::Gap(g) => Some(*g),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let Some(dot) = estimate_dot(&marks, &gaps) else {
+            return;
+        };
+        self.dot = Some(dot);
+        self.char_gap = estimate_char_gap(&gaps, dot);
+        history.into_iter().for_each(|r| self.push(r, out));
+    }
+}
+
+// splits sorted values at the largest ratio between neighbours, if larger than min_ratio
+fn split(sorted: &[f64], min_ratio: f64) -> Option<usize> {
+    sorted
+        .windows(2)
+        .enumerate()
+        .map(|(i, w)| (i + 1, w[1] / w[0].max(1e-6)))
+        .filter(|&(_, r)| r >= min_ratio)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+}
+
+fn estimate_dot(marks: &[f64], gaps: &[f64]) -> Option<f64> {
+    let mut m = marks.to_vec();
+    m.sort_by(|a, b| a.total_cmp(b));
+    if m.is_empty() {
+        return None;
+    }
+
+    if let Some(i) = split(&m, 2.0) {
+        // dots and dashes
+        let units = m[..i].iter().sum::<f64>() + m[i..].iter().sum::<f64>() / 3.0;
+        return Some(units / m.len() as f64);
+    }
+
+    // all the same kind; compare with the shortest gap, which is probably an element gap
+    let mean = m.iter().sum::<f64>() / m.len() as f64;
+    let shortest = gaps.iter().copied().reduce(f64::min).unwrap_or(0.1);
+    Some(if mean < 2.0 * shortest {
+        mean
+    } else {
+        mean / 3.0
+    })
+}
+
+fn estimate_char_gap(gaps: &[f64], dot: f64) -> f64 {
+    let mut g = gaps
+        .iter()
+        .copied()
+        .filter(|&g| g >= 2.0 * dot)
+        .collect::<Vec<_>>();
+    g.sort_by(|a, b| a.total_cmp(b));
+    // char gaps and word gaps, or only one of them
+    let g = match split(&g, 1.8) {
+        Some(i) => &g[..i],
+        None => &g[..],
+    };
+    if g.is_empty() {
+        3.0 * dot
+    } else {
+        (g.iter().sum::<f64>() / g.len() as f64).max(2.0 * dot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs() {
+        let dt = 0.001;
+        let mut runs = Runs::default();
+        let mut out = Vec::new();
+        let mut key = |on: bool, ms: usize, out: &mut Vec<Run>| {
+            (0..ms).for_each(|_| runs.step(on, dt, 0.01, out));
+        };
+        key(false, 100, &mut out);
+        key(true, 60, &mut out);
+        key(false, 3, &mut out); // dropout
+        key(true, 120, &mut out);
+        key(false, 60, &mut out);
+        key(true, 4, &mut out); // blip
+        key(false, 60, &mut out);
+        key(true, 60, &mut out);
+        key(false, 20, &mut out);
+
+        let approx = |a: &Run, b: Run| match (a, b) {
+            (Run::Mark(x), Run::Mark(y)) | (Run::Gap(x), Run::Gap(y)) => (x - y).abs() < 0.0015,
+            _ => false,
+        };
+        assert_eq!(out.len(), 3, "{out:?}");
+        assert!(approx(&out[0], Run::Mark(0.183)));
+        assert!(approx(&out[1], Run::Gap(0.124)));
+        assert!(approx(&out[2], Run::Mark(0.06)));
+    }
+
+    #[test]
+    fn test_estimate_dot() {
+        assert_eq!(estimate_dot(&[0.06, 0.18, 0.06], &[]), Some(0.06));
+        // only dashes
+        let d = estimate_dot(&[0.18, 0.18], &[0.06]).unwrap();
+        assert!((d - 0.06).abs() < 1e-9);
+        // farnsworth: char gaps are long, word gaps even longer
+        let g = estimate_char_gap(&[0.06, 0.6, 0.06, 1.4, 0.6], 0.06);
+        assert!((g - 0.6).abs() < 1e-9);
+    }
+}
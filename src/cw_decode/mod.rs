@@ -0,0 +1,287 @@
+mod classify;
+mod tone;
+
+use std::collections::VecDeque;
+
+use crate::morse::{Element, MorseChar};
+
+use classify::{Classifier, Runs, Slicer};
+
+const BLOCK_SECS: f32 = 0.002; // time resolution
+const DEMOD_BLOCKS: usize = 5; // ~100Hz bandwidth
+const SEARCH_BLOCKS: usize = 32;
+const RETUNE_HZ: f32 = 25.0;
+const RETUNE_RATIO: f64 = 4.0; // new peak over the power at the current tone
+const BACKLOG_SECS: usize = 10; // kept until the tone is found
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecoderConfig {
+    // range to search the tone in
+    pub min_freq: f32,
+    pub max_freq: f32,
+    // skips the search
+    pub freq: Option<f32>,
+}
+
+impl Default for DecoderConfig {
+    fn default() -> Self {
+        Self {
+            min_freq: 300.0,
+            max_freq: 1200.0,
+            freq: None,
+        }
+    }
+}
+
+/*
+    decodes CW audio into characters
+    finds the tone, slices it into marks and gaps, and follows the speed
+*/
+pub struct CWDecoder {
+    srate: usize,
+    block: usize, // in samples
+    search: Option<tone::ToneSearch>,
+    demod: Option<tone::Demod>,
+    backlog: VecDeque<f32>,
+
+    slicer: Slicer,
+    runs: Runs,
+    classifier: Classifier,
+}
+
+impl CWDecoder {
+    pub fn new(srate: usize) -> Self {
+        Self::with_config(srate, &DecoderConfig::default())
+    }
+
+    pub fn with_config(srate: usize, config: &DecoderConfig) -> Self {
+        let block = ((srate as f32 * BLOCK_SECS).round() as usize).max(1);
+        Self {
+            srate,
+            block,
+            search: config.freq.is_none().then(|| {
+                tone::ToneSearch::new(
+                    config.min_freq,
+                    config.max_freq,
+                    srate,
+                    block * SEARCH_BLOCKS,
+                )
+            }),
+            demod: config
+                .freq
+                .map(|f| tone::Demod::new(f, srate, block, DEMOD_BLOCKS)),
+            backlog: VecDeque::new(),
+
+            slicer: Slicer::new(block as f32 / srate as f32),
+            runs: Runs::default(),
+            classifier: Classifier::new(),
+        }
+    }
+
+    // frequency of the tone being decoded
+    pub fn freq(&self) -> Option<f32> {
+        self.demod.as_ref().map(|d| d.freq())
+    }
+
+    // estimated speed, in PARIS words per minute
+    pub fn wpm(&self) -> Option<f32> {
+        self.classifier.dot().map(|d| (1.2 / d) as f32)
+    }
+
+    // marks and gaps classified so far, with their lengths in seconds
+    pub fn take_elements(&mut self) -> Vec<(Element, f64)> {
+        self.classifier.take_elements()
+    }
+
+    // returns the characters completed so far; spaces are emitted after a word gap
+    pub fn push(&mut self, samples: &[f32]) -> Vec<MorseChar> {
+        let mut out = Vec::new();
+        for &x in samples {
+            self.search_tone(x, &mut out);
+            match &mut self.demod {
+                Some(d) => {
+                    if let Some(e) = d.push(x) {
+                        self.step(e, &mut out);
+                    }
+                }
+                None => {
+                    self.backlog.push_back(x);
+                    if self.backlog.len() > BACKLOG_SECS * self.srate {
+                        self.backlog.pop_front();
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    // end of the audio; returns the rest
+    pub fn finish(&mut self) -> Vec<MorseChar> {
+        // lets the last mark out of the window
+        let mut out = self.push(&vec![0.0; self.block * (DEMOD_BLOCKS + 1)]);
+
+        let mut runs = Vec::new();
+        self.runs.finish(self.classifier.min_run(), &mut runs);
+        runs.into_iter()
+            .for_each(|r| self.classifier.push(r, &mut out));
+        self.classifier.finish(&mut out);
+        out
+    }
+
+    fn search_tone(&mut self, x: f32, out: &mut Vec<MorseChar>) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        if !search.push(x) {
+            return;
+        }
+        let Some((f, power)) = search.peak() else {
+            return;
+        };
+        // stays on the tone unless another one is much stronger
+        if let Some(d) = &self.demod {
+            if (d.freq() - f).abs() < RETUNE_HZ || search.power_at(d.freq()) * RETUNE_RATIO > power
+            {
+                return;
+            }
+        }
+
+        // the audio before the tone was found is decoded first
+        let mut demod = tone::Demod::new(f, self.srate, self.block, DEMOD_BLOCKS);
+        for x in std::mem::take(&mut self.backlog) {
+            if let Some(e) = demod.push(x) {
+                self.step(e, out);
+            }
+        }
+        self.demod = Some(demod);
+    }
+
+    fn step(&mut self, (e, side): (f32, f32), out: &mut Vec<MorseChar>) {
+        let on = self.slicer.push(e, side);
+        let dt = self.block as f64 / self.srate as f64;
+
+        let mut runs = Vec::new();
+        self.runs.step(on, dt, self.classifier.min_run(), &mut runs);
+        runs.into_iter().for_each(|r| self.classifier.push(r, out));
+        if let Some(g) = self.runs.gap() {
+            self.classifier.gap(g, out);
+        }
+    }
+}
+
+// decodes the whole audio at once
+pub fn decode_samples(samples: &[f32], srate: usize) -> Vec<MorseChar> {
+    let mut decoder = CWDecoder::new(srate);
+    let mut v = decoder.push(samples);
+    v.extend(decoder.finish());
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cw_audio::{CWAudioPCM, NoiseConfig};
+    use crate::morse::{decode_to_string, CodeTable, DecodeMode, Fist, Timeline, Timing};
+    use std::str::FromStr;
+
+    const TEXT: &str = "CQ CQ DE JA1ABC JA1ABC K";
+
+    fn decode(pcm: &mut CWAudioPCM, srate: usize) -> String {
+        let codes = decode_samples(&pcm.render(), srate);
+        decode_to_string(&codes, DecodeMode::Table(CodeTable::International))
+    }
+
+    // characters differing from the text, by edit distance
+    fn errors(a: &str, b: &str) -> usize {
+        let a = a.chars().collect::<Vec<_>>();
+        let b = b.chars().collect::<Vec<_>>();
+        crate::util::edit_distance(&a, &b)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for (wpm, freq, srate) in [
+            (12.0, 500.0, 8000),
+            (20.0, 600.0, 8000),
+            (30.0, 800.0, 16000),
+            (40.0, 1000.0, 48000),
+        ] {
+            let mut pcm = CWAudioPCM::new(TEXT.to_string(), wpm, freq, srate);
+            assert_eq!(decode(&mut pcm, srate), TEXT, "{wpm} wpm");
+        }
+    }
+
+    #[test]
+    fn test_speed_and_freq() {
+        let srate = 8000;
+        let samples = CWAudioPCM::new(TEXT.to_string(), 25.0, 700.0, srate).render();
+        let mut decoder = CWDecoder::new(srate);
+        decoder.push(&samples);
+        decoder.finish();
+        let wpm = decoder.wpm().unwrap();
+        assert!((wpm - 25.0).abs() < 2.0, "{wpm}");
+        let freq = decoder.freq().unwrap();
+        assert!((freq - 700.0).abs() < 10.0, "{freq}");
+
+        let codes = crate::morse::get_morse_str(TEXT.to_string());
+        let elements = decoder.take_elements();
+        assert!(elements
+            .iter()
+            .map(|e| e.0)
+            .eq(crate::morse::elements(&codes)));
+    }
+
+    #[test]
+    fn test_farnsworth_and_fist() {
+        let srate = 8000;
+        let codes = crate::morse::get_morse_str(TEXT.to_string());
+
+        let timeline = Timeline::new(&codes, &Timing::farnsworth(18.0, 8.0));
+        let mut pcm = CWAudioPCM::from_timeline(&timeline, 600.0, srate);
+        assert_eq!(decode(&mut pcm, srate), TEXT);
+
+        let fist = Fist {
+            seed: Some(1),
+            ..Fist::from_str("straight").unwrap()
+        };
+        let timeline = Timeline::with_fist(&codes, &Timing::new(20.0), &fist);
+        let mut pcm = CWAudioPCM::from_timeline(&timeline, 600.0, srate);
+        assert_eq!(decode(&mut pcm, srate), TEXT);
+    }
+
+    #[test]
+    fn test_noise() {
+        let srate = 8000;
+        // static crashes can take out a character or two
+        for (snr, crashes, max_errors) in [(10.0, 0.0, 0), (3.0, 0.0, 2), (10.0, 0.2, 3)] {
+            let noise = NoiseConfig {
+                crashes,
+                seed: Some(1),
+                ..NoiseConfig::new(snr)
+            };
+            let mut pcm =
+                CWAudioPCM::new(format!("  {TEXT}  "), 20.0, 650.0, srate).with_noise(&noise);
+            let text = decode(&mut pcm, srate);
+            let e = errors(text.trim(), TEXT);
+            assert!(e <= max_errors, "{snr} dB: {text} ({e} errors)");
+        }
+    }
+
+    #[test]
+    fn test_streaming() {
+        let srate = 8000;
+        let samples = CWAudioPCM::new(TEXT.to_string(), 20.0, 600.0, srate).render();
+
+        let mut decoder = CWDecoder::new(srate);
+        let mut codes = Vec::new();
+        let mut decoded_early = 0;
+        for chunk in samples.chunks(160) {
+            codes.extend(decoder.push(chunk));
+            decoded_early = codes.len();
+        }
+        codes.extend(decoder.finish());
+        // only the last character waits for the end
+        assert!(codes.len() - decoded_early <= 1);
+        assert_eq!(codes, decode_samples(&samples, srate));
+    }
+}
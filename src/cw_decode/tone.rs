@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+// complex oscillator, advanced one sample at a time
+#[derive(Clone)]
+struct Phasor {
+    z: (f64, f64),
+    w: (f64, f64),
+}
+
+impl Phasor {
+    fn new(freq: f32, srate: usize) -> Self {
+        let d = 2.0 * PI * freq as f64 / srate as f64;
+        Self {
+            z: (1.0, 0.0),
+            w: (d.cos(), -d.sin()),
+        }
+    }
+
+    // x * z, then advances
+    fn mix(&mut self, x: f32, acc: &mut (f64, f64)) {
+        let x = x as f64;
+        acc.0 += x * self.z.0;
+        acc.1 += x * self.z.1;
+        let (a, b) = self.z;
+        self.z = (a * self.w.0 - b * self.w.1, a * self.w.1 + b * self.w.0);
+    }
+
+    // keeps the magnitude from drifting
+    fn normalize(&mut self) {
+        let m = self.z.0.hypot(self.z.1);
+        self.z = (self.z.0 / m, self.z.1 / m);
+    }
+}
+
+/*
+    finds the tone frequency; Goertzel power of each candidate over a window,
+    averaged across windows
+*/
+pub(super) struct ToneSearch {
+    freqs: Vec<f32>,
+    phasors: Vec<Phasor>,
+    sums: Vec<(f64, f64)>,
+    spectrum: Vec<f64>,
+    windows: usize,
+    n: usize,
+    window: usize,
+}
+
+const SEARCH_STEP: f32 = 10.0; // Hz
+const SEARCH_SMOOTHING: f64 = 0.1;
+const MIN_WINDOWS: usize = 4; // averaged before any peak is taken
+const PROMINENCE: f64 = 6.0; // peak power over the power around it
+const AROUND: (f32, f32) = (80.0, 200.0); // Hz away from the peak
+
+impl ToneSearch {
+    pub fn new(min_freq: f32, max_freq: f32, srate: usize, window: usize) -> Self {
+        let n = ((max_freq - min_freq) / SEARCH_STEP).max(0.0) as usize + 1;
+        let freqs = (0..n)
+            .map(|i| min_freq + i as f32 * SEARCH_STEP)
+            .collect::<Vec<_>>();
+        Self {
+            phasors: freqs.iter().map(|&f| Phasor::new(f, srate)).collect(),
+            sums: vec![(0.0, 0.0); n],
+            spectrum: vec![0.0; n],
+            freqs,
+            windows: 0,
+            n: 0,
+            window,
+        }
+    }
+
+    // true when a window is finished
+    pub fn push(&mut self, x: f32) -> bool {
+        self.phasors
+            .iter_mut()
+            .zip(self.sums.iter_mut())
+            .for_each(|(p, s)| p.mix(x, s));
+        self.n += 1;
+        if self.n < self.window {
+            return false;
+        }
+
+        // plain average at first, then exponential
+        let a = (1.0 / (self.windows + 1) as f64).max(SEARCH_SMOOTHING);
+        for ((s, p), ph) in self
+            .spectrum
+            .iter_mut()
+            .zip(self.sums.iter_mut())
+            .zip(self.phasors.iter_mut())
+        {
+            let power = p.0 * p.0 + p.1 * p.1;
+            *s += (power - *s) * a;
+            *p = (0.0, 0.0);
+            ph.normalize();
+        }
+        self.windows += 1;
+        self.n = 0;
+        true
+    }
+
+    // averaged power at the nearest candidate
+    pub fn power_at(&self, freq: f32) -> f64 {
+        let i = ((freq - self.freqs[0]) / SEARCH_STEP).round().max(0.0) as usize;
+        self.spectrum[i.min(self.spectrum.len() - 1)]
+    }
+
+    // frequency and power of the strongest tone, if it stands out
+    pub fn peak(&self) -> Option<(f32, f64)> {
+        if self.windows < MIN_WINDOWS {
+            return None;
+        }
+        let (i, &max) = self
+            .spectrum
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+
+        // against the noise near the peak, as the band may be filtered
+        let (near, far) = (
+            (AROUND.0 / SEARCH_STEP) as usize,
+            (AROUND.1 / SEARCH_STEP) as usize,
+        );
+        let around = (near..=far)
+            .flat_map(|d| [i.checked_sub(d), Some(i + d)])
+            .filter_map(|j| self.spectrum.get(j?))
+            .collect::<Vec<_>>();
+        let mean = around.iter().copied().sum::<f64>() / around.len().max(1) as f64;
+        if max <= 0.0 || max < mean * PROMINENCE {
+            return None;
+        }
+
+        // parabolic interpolation between the bins
+        let offset = match (
+            self.spectrum.get(i.wrapping_sub(1)),
+            self.spectrum.get(i + 1),
+        ) {
+            (Some(&l), Some(&r)) => {
+                let d = l - 2.0 * max + r;
+                if d < 0.0 {
+                    (0.5 * (l - r) / d).clamp(-0.5, 0.5)
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        };
+        Some((self.freqs[i] + offset as f32 * SEARCH_STEP, max))
+    }
+}
+
+// sliding correlation with a tone over the last few blocks (a sliding Goertzel)
+struct Channel {
+    phasor: Phasor,
+    sum: (f64, f64),
+    blocks: VecDeque<(f64, f64)>,
+    total: (f64, f64),
+}
+
+impl Channel {
+    fn new(freq: f32, srate: usize, len: usize) -> Self {
+        Self {
+            phasor: Phasor::new(freq, srate),
+            sum: (0.0, 0.0),
+            blocks: VecDeque::with_capacity(len + 1),
+            total: (0.0, 0.0),
+        }
+    }
+
+    // amplitude over the window, at the end of a block
+    fn end_block(&mut self, len: usize, n: f64) -> f32 {
+        self.phasor.normalize();
+        let s = std::mem::take(&mut self.sum);
+        self.blocks.push_back(s);
+        self.total.0 += s.0;
+        self.total.1 += s.1;
+        if self.blocks.len() > len {
+            let old = self.blocks.pop_front().unwrap_or_default();
+            self.total.0 -= old.0;
+            self.total.1 -= old.1;
+        }
+        (2.0 * self.total.0.hypot(self.total.1) / n) as f32
+    }
+}
+
+const SIDE_OFFSET: f32 = 200.0; // Hz, to see broadband noise like static crashes
+
+/*
+    magnitude of the tone, one value per block
+    with the magnitude beside the tone, which rises with static crashes but not with the tone
+*/
+pub(super) struct Demod {
+    freq: f32,
+    channels: [Channel; 3], // tone, below and above
+    block: usize,
+    n: usize,
+    len: usize, // blocks in the window
+}
+
+impl Demod {
+    pub fn new(freq: f32, srate: usize, block: usize, len: usize) -> Self {
+        Self {
+            freq,
+            channels: [
+                Channel::new(freq, srate, len),
+                Channel::new(freq - SIDE_OFFSET, srate, len),
+                Channel::new(freq + SIDE_OFFSET, srate, len),
+            ],
+            block,
+            n: 0,
+            len,
+        }
+    }
+
+    pub fn freq(&self) -> f32 {
+        self.freq
+    }
+
+    // (tone, beside the tone)
+    pub fn push(&mut self, x: f32) -> Option<(f32, f32)> {
+        self.channels
+            .iter_mut()
+            .for_each(|c| c.phasor.mix(x, &mut c.sum));
+        self.n += 1;
+        if self.n < self.block {
+            return None;
+        }
+        self.n = 0;
+
+        let n = (self.block * self.len) as f64;
+        let [tone, lower, upper] = &mut self.channels;
+        let e = tone.end_block(self.len, n);
+        let side = lower
+            .end_block(self.len, n)
+            .max(upper.end_block(self.len, n));
+        // nothing until the window is filled
+        (tone.blocks.len() == self.len).then_some((e, side))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone_search() {
+        let srate = 8000;
+        let mut search = ToneSearch::new(300.0, 1200.0, srate, 512);
+        for i in 0..srate {
+            let x = (2.0 * PI * 737.0 * i as f64 / srate as f64).sin() as f32;
+            search.push(x);
+        }
+        let (f, power) = search.peak().unwrap();
+        assert_eq!(search.power_at(f), power);
+        assert!((f - 737.0).abs() < 3.0, "{f}");
+
+        // nothing stands out in silence
+        let mut search = ToneSearch::new(300.0, 1200.0, srate, 512);
+        (0..srate).for_each(|_| {
+            search.push(0.0);
+        });
+        assert_eq!(search.peak(), None);
+    }
+
+    #[test]
+    fn test_demod() {
+        let srate = 8000;
+        let mut demod = Demod::new(600.0, srate, 16, 5);
+        let v = (0..srate)
+            .filter_map(|i| {
+                let x = (2.0 * PI * 600.0 * i as f64 / srate as f64).sin() as f32;
+                demod.push(0.5 * x)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(v.len(), srate / 16 - 4);
+        assert!(v
+            .iter()
+            .all(|(e, side)| (e - 0.5).abs() < 0.01 && *side < 0.01));
+    }
+}
@@ -1,4 +1,8 @@
 pub mod bot;
 pub mod cw_audio;
+pub mod cw_decode;
 pub mod modes;
 pub mod morse;
+#[cfg(test)]
+mod test_util;
+pub mod util;
@@ -1,4 +1,5 @@
-use std::{fs::File, io::BufReader, usize};
+use anyhow::Context as _;
+use std::{fs::File, io::BufReader};
 
 use serenity::framework::StandardFramework;
 use serenity::prelude::Client;
@@ -64,6 +65,35 @@ async fn main() -> anyhow::Result<()> {
         .await
         .expect("failed to create table");
 
+    sqlx::query("create table if not exists guild_config (id text primary key, volume REAL not null default 1)")
+        .execute(&db)
+        .await
+        .expect("failed to create table");
+
+    // columns added later; sqlite has no "add column if not exists"
+    for q in [
+        "alter table cw_speed add column code_table text not null default 'international'",
+        "alter table cw_speed add column effective_speed REAL",
+        "alter table cw_speed add column fist text not null default 'machine'",
+        "alter table cw_speed add column rise_ms REAL not null default 10",
+        "alter table cw_speed add column envelope text not null default 'cosine'",
+        "alter table cw_speed add column waveform text not null default 'sine'",
+        "alter table cw_speed add column volume REAL not null default 1",
+        "alter table cw_speed add column pan REAL",
+        "alter table guild_config add column queue integer not null default 0",
+        "alter table cw_speed add column weight REAL not null default 0.5",
+        "alter table cw_speed add column dash_ratio REAL not null default 3",
+        "alter table cw_speed add column reference_word text not null default 'paris'",
+        "alter table cw_speed add column wabun_markers integer not null default 1",
+    ] {
+        match sqlx::query(q).execute(&db).await {
+            Err(sqlx::Error::Database(e)) if e.message().starts_with("duplicate column name") => (),
+            r => {
+                r.with_context(|| format!("migration failed: {}", q))?;
+            }
+        }
+    }
+
     use serenity::model::gateway::GatewayIntents;
     let intents = GatewayIntents::non_privileged() | GatewayIntents::MESSAGE_CONTENT;
 
@@ -1,12 +1,13 @@
 use super::{number::LessonAnswerContestNumber, LessonAnswerBox};
-use rand::{self, Rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::ops::RangeInclusive;
 pub struct ACAGNumberGen {
     acag_nr: Vec<String>,
+    rng: StdRng,
 }
 
 impl ACAGNumberGen {
-    pub fn new() -> Self {
+    pub fn new(rng: StdRng) -> Self {
         let acag_nr = [
             //北海道
             filter_and_to_string_numbers(
@@ -311,13 +312,13 @@ impl ACAGNumberGen {
             ),
         ]
         .concat();
-        Self { acag_nr }
+        Self { acag_nr, rng }
     }
 }
 
 impl Default for ACAGNumberGen {
     fn default() -> Self {
-        Self::new()
+        Self::new(StdRng::from_entropy())
     }
 }
 
@@ -335,11 +336,10 @@ impl Iterator for ACAGNumberGen {
     type Item = LessonAnswerBox;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut rng = rand::thread_rng();
-        let idx = rng.gen_range(0..self.acag_nr.len());
+        let idx = self.rng.gen_range(0..self.acag_nr.len());
         let s = self.acag_nr[idx].clone();
 
-        let s = match rand::random::<u8>() {
+        let s = match self.rng.gen::<u8>() {
             0..=99 => s + "H",
             100..=199 => s + "M",
             200..=224 => s + "L",
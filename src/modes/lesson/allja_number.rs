@@ -1,12 +1,13 @@
-use rand::{self, Rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use super::{number::LessonAnswerContestNumber, LessonAnswerBox};
 pub struct AllJANumberGen {
     allja_nr: Vec<String>,
+    rng: StdRng,
 }
 
 impl AllJANumberGen {
-    pub fn new() -> Self {
+    pub fn new(rng: StdRng) -> Self {
         let allja_nr = [
             (101..=114).map(|x| x.to_string()).collect::<Vec<_>>(),
             (2..=48)
@@ -20,13 +21,13 @@ impl AllJANumberGen {
                 .collect::<Vec<_>>(),
         ]
         .concat();
-        Self { allja_nr }
+        Self { allja_nr, rng }
     }
 }
 
 impl Default for AllJANumberGen {
     fn default() -> Self {
-        Self::new()
+        Self::new(StdRng::from_entropy())
     }
 }
 
@@ -34,10 +35,9 @@ impl Iterator for AllJANumberGen {
     type Item = LessonAnswerBox;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut rng = rand::thread_rng();
-        let s = self.allja_nr[rng.gen_range(0..self.allja_nr.len())].clone();
+        let s = self.allja_nr[self.rng.gen_range(0..self.allja_nr.len())].clone();
 
-        let s = match rand::random::<u8>() {
+        let s = match self.rng.gen::<u8>() {
             0..=99 => s + "H",
             100..=199 => s + "M",
             200..=224 => s + "L",
@@ -49,7 +49,7 @@ impl Iterator for AllJANumberGen {
 
 #[test]
 fn test_allja_number() {
-    let mut gen = AllJANumberGen::new();
+    let mut gen = AllJANumberGen::new(StdRng::from_entropy());
     for _ in 0..100 {
         println!("{}", gen.next().unwrap().into_str());
     }
@@ -1,3 +1,5 @@
+use rand::{rngs::StdRng, Rng};
+
 use crate::modes::lesson::rand_char;
 
 use super::LessonAnswerBox;
@@ -7,44 +9,65 @@ const ALNUM: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
 const NUM: &str = "0123456789";
 const JA_PRF: &str = "AEFGHIJKLMNOPQRS";
 
-pub struct JaCallsignGen;
+pub struct JaCallsignGen {
+    rng: StdRng,
+}
+
+impl JaCallsignGen {
+    pub fn new(rng: StdRng) -> Self {
+        Self { rng }
+    }
+}
+
 impl Iterator for JaCallsignGen {
     type Item = LessonAnswerBox;
 
     fn next(&mut self) -> Option<Self::Item> {
         // TODO: improve algorithm
-        let s = match rand::random::<u8>() {
+        let s = match self.rng.gen::<u8>() {
             0..=13 => {
                 "7".to_string()
-                    + rand_char("JKLMN")
-                    + rand_char(NUM)
-                    + rand_char(ALPHA)
-                    + rand_char(ALPHA)
-                    + rand_char(ALPHA)
+                    + rand_char(&mut self.rng, "JKLMN")
+                    + rand_char(&mut self.rng, NUM)
+                    + rand_char(&mut self.rng, ALPHA)
+                    + rand_char(&mut self.rng, ALPHA)
+                    + rand_char(&mut self.rng, ALPHA)
             }
             14 => {
                 "8".to_string()
-                    + rand_char("JN")
-                    + rand_char(NUM)
-                    + rand_char(ALNUM)
-                    + rand_char(ALNUM)
-                    + rand_char(ALNUM)
+                    + rand_char(&mut self.rng, "JN")
+                    + rand_char(&mut self.rng, NUM)
+                    + rand_char(&mut self.rng, ALNUM)
+                    + rand_char(&mut self.rng, ALNUM)
+                    + rand_char(&mut self.rng, ALNUM)
+            }
+            15..=18 => {
+                "JA".to_owned()
+                    + rand_char(&mut self.rng, NUM)
+                    + rand_char(&mut self.rng, ALPHA)
+                    + rand_char(&mut self.rng, ALPHA)
+            }
+            19 => {
+                "JR6".to_owned() + rand_char(&mut self.rng, ALPHA) + rand_char(&mut self.rng, ALPHA)
+            }
+            20..=29 => {
+                "JD1".to_owned()
+                    + rand_char(&mut self.rng, ALPHA)
+                    + rand_char(&mut self.rng, ALPHA)
+                    + rand_char(&mut self.rng, ALPHA)
             }
-            15..=18 => "JA".to_owned() + rand_char(NUM) + rand_char(ALPHA) + rand_char(ALPHA),
-            19 => "JR6".to_owned() + rand_char(ALPHA) + rand_char(ALPHA),
-            20..=29 => "JD1".to_owned() + rand_char(ALPHA) + rand_char(ALPHA) + rand_char(ALPHA),
             30..=255 => {
                 "J".to_string()
-                    + rand_char(JA_PRF)
-                    + rand_char(NUM)
-                    + rand_char(ALPHA)
-                    + rand_char(ALPHA)
-                    + rand_char(ALPHA)
+                    + rand_char(&mut self.rng, JA_PRF)
+                    + rand_char(&mut self.rng, NUM)
+                    + rand_char(&mut self.rng, ALPHA)
+                    + rand_char(&mut self.rng, ALPHA)
+                    + rand_char(&mut self.rng, ALPHA)
             }
         };
 
-        let s = if rand::random::<u8>() < 50 {
-            s + "/" + &rand_char(NUM)
+        let s = if self.rng.gen::<u8>() < 50 {
+            s + "/" + rand_char(&mut self.rng, NUM)
         } else {
             s
         };
@@ -1,14 +1,15 @@
 use anyhow::Context;
-use rand::seq::SliceRandom;
+use rand::{rngs::StdRng, seq::SliceRandom};
 
 use super::LessonAnswerBox;
 
 pub struct FileSourceGen {
     data: Vec<String>,
+    rng: StdRng,
 }
 const LESSON_TXT_DIR: &str = "./lesson_txt/";
 impl FileSourceGen {
-    pub fn new(filename: &str) -> anyhow::Result<Self> {
+    pub fn new(filename: &str, rng: StdRng) -> anyhow::Result<Self> {
         anyhow::ensure!(!filename.contains('/'), "invalid filename");
 
         let p = std::path::Path::new(LESSON_TXT_DIR).join(filename);
@@ -26,7 +27,7 @@ impl FileSourceGen {
 
         let text = std::fs::read_to_string(p)?;
         let data = text.lines().map(|x| x.to_owned()).collect::<Vec<_>>();
-        Ok(Self { data })
+        Ok(Self { data, rng })
     }
 }
 
@@ -34,8 +35,7 @@ impl Iterator for FileSourceGen {
     type Item = LessonAnswerBox;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut rng = rand::thread_rng();
-        let v = self.data.choose(&mut rng)?;
+        let v = self.data.choose(&mut self.rng)?;
         Some(Box::new(v.clone()))
     }
 }
@@ -1,6 +1,6 @@
 use super::{LessonAnswer, LessonAnswerBox};
 use kanaria::string::UCSStr;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng};
 use unicode_normalization::UnicodeNormalization;
 
 // カタカナ（清音、濁音、半濁音を含む）
@@ -44,13 +44,18 @@ fn normalize_japanese(s: &str) -> String {
     s.nfkd().collect::<String>()
 }
 
-pub struct JapaneseFiveCharGen;
+pub struct JapaneseFiveCharGen {
+    rng: StdRng,
+}
 
 impl JapaneseFiveCharGen {
-    fn random_char() -> char {
-        let mut rng = rand::thread_rng();
+    pub fn new(rng: StdRng) -> Self {
+        Self { rng }
+    }
+
+    fn random_char(&mut self) -> char {
         let chars: Vec<char> = KATAKANA.chars().collect();
-        chars[rng.gen_range(0..chars.len())]
+        chars[self.rng.gen_range(0..chars.len())]
     }
 }
 
@@ -62,7 +67,7 @@ impl Iterator for JapaneseFiveCharGen {
 
         // 5文字を生成
         for _ in 0..5 {
-            result.push(Self::random_char());
+            result.push(self.random_char());
         }
 
         Some(Box::new(NormalizedJapaneseAnswer::new(result)))
@@ -72,10 +77,11 @@ impl Iterator for JapaneseFiveCharGen {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::SeedableRng;
 
     #[test]
     fn test_japanese_five_char_gen() {
-        let mut gen = JapaneseFiveCharGen;
+        let mut gen = JapaneseFiveCharGen::new(StdRng::from_entropy());
 
         for _ in 0..10 {
             let result = gen.next().unwrap();
@@ -3,6 +3,7 @@ pub mod allja_number;
 pub mod callsign;
 pub mod file;
 pub mod japanese;
+pub(crate) mod notation;
 mod number;
 
 use anyhow::Context as _;
@@ -11,9 +12,10 @@ use std::iter::Iterator;
 use std::sync::{Arc, Mutex};
 
 use rand::Rng;
+use serenity::http::Http;
 use serenity::model::channel::Message;
 use serenity::model::channel::ReactionType;
-use serenity::model::prelude::{GuildId, UserId};
+use serenity::model::prelude::{ChannelId, GuildId, UserId};
 use serenity::prelude::{Context, Mentionable};
 use songbird::constants::SAMPLE_RATE_RAW;
 
@@ -21,7 +23,8 @@ pub trait LessonAnswer: Send {
     // given uppercase
     fn check(&self, s: &str) -> bool;
 
-    fn into_str<'a>(&'a self) -> &'a str;
+    #[allow(clippy::wrong_self_convention)]
+    fn into_str(&self) -> &str;
 
     fn clone_boxed(&self) -> Box<dyn LessonAnswer>;
 }
@@ -32,7 +35,7 @@ impl LessonAnswer for String {
     }
 
     fn into_str(&self) -> &str {
-        &self
+        self
     }
 
     fn clone_boxed(&self) -> Box<dyn LessonAnswer> {
@@ -43,19 +46,78 @@ impl LessonAnswer for String {
 pub type LessonAnswerBox = Box<dyn LessonAnswer>;
 pub type LessonGen = Box<dyn Iterator<Item = LessonAnswerBox> + Send>;
 
+// probset is name[:args], e.g. call_ja or file:foo.txt
+// the same seed gives the same questions
+pub fn get_lesson_gen(probset: &str, seed: Option<u64>) -> anyhow::Result<LessonGen> {
+    // TODO: use braces to support nesting
+    let (probset_name, probset_args_str) = probset.split_once(':').unwrap_or((probset, ""));
+
+    let rng = crate::util::rng(seed);
+    let gen: LessonGen = match probset_name {
+        "call_ja" => Box::new(callsign::JaCallsignGen::new(rng)),
+        "file" => Box::new(file::FileSourceGen::new(probset_args_str, rng)?),
+        "nr_allja" => Box::new(allja_number::AllJANumberGen::new(rng)),
+        "nr_acag" => Box::new(acag_number::ACAGNumberGen::new(rng)),
+        "rand5_jp" => Box::new(japanese::JapaneseFiveCharGen::new(rng)),
+        _ => {
+            anyhow::bail!(
+                "unknown probset.\n".to_owned()
+                    + "available selections are: call_ja, file, nr_allja, nr_acag, rand5_jp"
+            )
+        }
+    };
+    Ok(gen)
+}
+
+// how questions are sent, other than speed and freq
+pub struct LessonOptions {
+    pub effective_speed: Option<f32>,
+    pub snr_range: Option<std::ops::RangeInclusive<f32>>, // None for clean audio
+    pub noise: crate::cw_audio::NoiseConfig,              // snr_db is picked from snr_range
+    pub effects: crate::cw_audio::Effects,
+    pub fist: crate::morse::Fist,
+    pub tone: crate::cw_audio::Tone,
+    pub table: crate::morse::CodeTable,
+    pub wabun_markers: bool,
+    pub volume: f32,
+    // questions are posted as text and answered in dot/dash notation
+    pub notation: bool,
+}
+
+impl Default for LessonOptions {
+    fn default() -> Self {
+        Self {
+            effective_speed: None,
+            snr_range: None,
+            noise: crate::cw_audio::NoiseConfig::new(0.0),
+            effects: Default::default(),
+            fist: Default::default(),
+            tone: Default::default(),
+            table: Default::default(),
+            wabun_markers: true,
+            volume: 1.0,
+            notation: false,
+        }
+    }
+}
+
 pub struct LessonModeState {
     speed_range: std::ops::RangeInclusive<f32>,
     freq_range: std::ops::RangeInclusive<f32>,
+    opts: LessonOptions,
 
     last_ans: Option<Box<dyn LessonAnswer>>,
     last_freq: f32,
     last_speed: f32,
+    last_snr: Option<f32>,
 
     gen: LessonGen,
+    txt: Option<(Arc<Http>, ChannelId)>, // where questions are posted in notation lessons
 
     answered: bool, // to check 1st AC
     is_advancing: bool,
     next_ftr_token: Option<tokio_util::sync::CancellationToken>,
+    track: Option<songbird::tracks::TrackHandle>, // the question playing; other tracks are left
 
     current_repeat: usize,
     repeat_counts: Vec<usize>,
@@ -66,18 +128,23 @@ impl LessonModeState {
     pub fn new(
         speed_range: std::ops::RangeInclusive<f32>,
         freq_range: std::ops::RangeInclusive<f32>,
+        opts: LessonOptions,
         gen: LessonGen,
     ) -> Self {
         Self {
             speed_range,
             freq_range,
+            opts,
             last_ans: None,
             last_freq: 0.,
             last_speed: 0.,
+            last_snr: None,
             gen,
+            txt: None,
             answered: false,
             is_advancing: false,
             next_ftr_token: None,
+            track: None,
 
             current_repeat: 0,
             repeat_counts: Vec::new(),
@@ -98,11 +165,17 @@ impl Drop for LessonModeState {
 pub async fn start(
     ctx: &Context,
     guild: GuildId,
+    txt_ch: ChannelId,
     state: Arc<Mutex<LessonModeState>>,
 ) -> anyhow::Result<()> {
     let man = songbird::get(ctx).await.expect("init songbird").clone();
 
     let call = man.get(guild).context("not in call")?;
+    state
+        .lock()
+        .or_else(|_| anyhow::bail!("lock failed"))
+        .context("internal error")?
+        .txt = Some((ctx.http.clone(), txt_ch));
     play_next(call, state).await?;
     Ok(())
 }
@@ -115,6 +188,9 @@ pub fn end(state: Arc<Mutex<LessonModeState>>) -> anyhow::Result<String> {
     if let Some(t) = st.next_ftr_token.take() {
         t.cancel()
     }
+    if let Some(t) = st.track.take() {
+        let _ = t.stop();
+    }
 
     // add last one
     let c = st.current_repeat;
@@ -140,7 +216,7 @@ pub fn end(state: Arc<Mutex<LessonModeState>>) -> anyhow::Result<String> {
     );
 
     let mut v = st.user_count.iter().collect::<Vec<_>>();
-    v.sort_by(|a, b| b.1 .1.cmp(&a.1 .1));
+    v.sort_by_key(|a| std::cmp::Reverse(a.1 .1));
 
     for (name, (correct, first)) in v {
         result_text.push_str(&format!("{}: {} / {}\n", name.mention(), first, correct,));
@@ -151,12 +227,28 @@ pub fn end(state: Arc<Mutex<LessonModeState>>) -> anyhow::Result<String> {
     Ok(result_text)
 }
 
+// the reply as an answer, whether it is correct, and its marks in notation lessons
+// None if it is not an answer, i.e. not in notation in a notation lesson
+fn read_reply(
+    content: &str,
+    ans: &dyn LessonAnswer,
+    opts: &LessonOptions,
+) -> Option<(String, bool, Option<String>)> {
+    if !opts.notation {
+        let s = content.to_uppercase();
+        let correct = ans.check(&s);
+        return Some((s, correct, None));
+    }
+    let (s, marks, correct) = notation::read_reply(content, ans.into_str(), opts.table)?;
+    Some((s, correct, Some(marks)))
+}
+
 pub async fn on_message(
     ctx: &Context,
     msg: &Message,
     state: Arc<Mutex<LessonModeState>>,
 ) -> anyhow::Result<()> {
-    let (s, ans, answered) = {
+    let (s, correct, marks, ans, answered) = {
         let mut st = state
             .lock()
             .or_else(|_| anyhow::bail!("lock failed"))
@@ -165,24 +257,26 @@ pub async fn on_message(
         // always insert
         st.user_count.entry(msg.author.id).or_insert((0, 0));
 
-        let s = msg.content.to_uppercase();
-
         let ans = match &st.last_ans {
             None => return Ok(()),
             // Some(ans) => (*ans).clone(),
             Some(ans) => ans.clone_boxed(),
         };
 
+        let Some((s, correct, marks)) = read_reply(&msg.content, ans.as_ref(), &st.opts) else {
+            return Ok(());
+        };
+
         let answered = st.answered;
-        if ans.check(&s) {
+        if correct {
             st.answered = true;
         }
 
         drop(st);
-        (s, ans, answered)
+        (s, correct, marks, ans, answered)
     };
 
-    if ans.check(&s) {
+    if correct {
         msg.react(
             &ctx.http,
             if answered {
@@ -211,9 +305,14 @@ pub async fn on_message(
         let call = man
             .get(msg.guild_id.context("not in guild")?)
             .context("not in call")?;
+        if let Some(t) = state
+            .lock()
+            .or_else(|_| anyhow::bail!("lock failed"))
+            .context("internal error")?
+            .track
+            .take()
         {
-            let mut handler = call.lock().await;
-            handler.stop();
+            let _ = t.stop();
         }
 
         let next_token = {
@@ -254,6 +353,12 @@ pub async fn on_message(
         msg.react(&ctx.http, ReactionType::from('❌'))
             .await
             .context("react failed")?;
+        if let Some(marks) = marks {
+            msg.channel_id
+                .say(&ctx.http, marks)
+                .await
+                .context("send failed")?;
+        }
     }
     Ok(())
 }
@@ -265,12 +370,43 @@ async fn play(
     let mut st = state.lock().or_else(|_| anyhow::bail!("lock failed"))?;
     let speed = st.last_speed;
     let freq = st.last_freq;
+    let snr = st.last_snr;
+    let effects = st.opts.effects.clone();
+    let noise = st.opts.noise.clone();
+    let tone = st.opts.tone.clone();
+    let volume = st.opts.volume;
     let s = &st.last_ans;
     let s = match s {
         None => return Ok(()),
         Some(s) => " ".to_string() + s.into_str(), // to keep margin between last playback
     };
 
+    if st.opts.notation {
+        st.current_repeat += 1;
+        let (http, ch) = st.txt.clone().context("no text channel")?;
+        let content = format!("encode: **{}**", s.trim());
+        tokio::spawn(async move {
+            if let Err(e) = ch.say(&http, content).await {
+                log::error!("send failed: {}", e);
+            }
+        });
+        return Ok(());
+    }
+
+    let codes = crate::morse::get_morse_str_with(
+        s,
+        &crate::morse::EncodeOptions {
+            wabun_markers: st.opts.wabun_markers,
+            table: st.opts.table,
+        },
+    );
+
+    let timing = crate::morse::Timing {
+        effective_wpm: st.opts.effective_speed,
+        ..crate::morse::Timing::new(speed)
+    };
+    let timeline = crate::morse::Timeline::with_fist(&codes, &timing, &st.opts.fist);
+
     let token = tokio_util::sync::CancellationToken::new();
     if let Some(t) = st.next_ftr_token.replace(token.clone()) {
         t.cancel()
@@ -278,23 +414,38 @@ async fn play(
 
     drop(st);
 
-    let delay_time =
-        crate::cw_audio::CWAudioPCM::get_duration(&s, speed) + std::time::Duration::from_secs(10);
+    let delay_time = timeline.duration() + std::time::Duration::from_secs(10);
 
     tokio::spawn(async move {
         loop {
             {
-                let mut handler = call.lock().await;
-                let source =
-                    crate::cw_audio::CWAudioPCM::new(s.clone(), speed, freq, SAMPLE_RATE_RAW)
-                        .to_input();
-                handler.play_only_source(source);
+                // built before taking the call, which is shared with the voice driver
+                let mut pcm =
+                    crate::cw_audio::CWAudioPCM::from_timeline(&timeline, freq, SAMPLE_RATE_RAW)
+                        .with_tone(&tone)
+                        .with_volume(volume)
+                        .with_effects(&effects);
+                if let Some(snr) = snr {
+                    pcm = pcm.with_noise(&crate::cw_audio::NoiseConfig {
+                        snr_db: snr,
+                        ..noise.clone()
+                    });
+                }
+                let source = pcm.to_input();
+
+                // replaces the previous question, but not what others play
+                let previous = state.lock().ok().and_then(|mut st| st.track.take());
+                if let Some(t) = previous {
+                    let _ = t.stop();
+                }
+                let track = call.lock().await.play_source(source);
 
                 state
                     .lock()
                     .or_else(|_| anyhow::bail!("lock failed"))
                     .map(|mut st| {
                         st.current_repeat += 1;
+                        st.track = Some(track);
                     })
                     .ok();
             }
@@ -339,13 +490,51 @@ pub async fn play_next(
         state.last_ans = Some(next_str);
         state.last_speed = rand::thread_rng().gen_range(state.speed_range.clone());
         state.last_freq = rand::thread_rng().gen_range(state.freq_range.clone());
+        state.last_snr = state
+            .opts
+            .snr_range
+            .clone()
+            .map(|r| rand::thread_rng().gen_range(r));
         state.answered = false;
     }
 
     play(call, state).await
 }
 
-fn rand_char(s: &str) -> &str {
-    let i = rand::thread_rng().gen_range(0..s.len());
+fn rand_char<'a>(rng: &mut impl Rng, s: &'a str) -> &'a str {
+    let i = rng.gen_range(0..s.len());
     &s[i..i + 1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_reply() {
+        let ans = "CQ K".to_string();
+        let opts = LessonOptions::default();
+        assert_eq!(
+            read_reply("cq k", &ans, &opts),
+            Some(("CQ K".into(), true, None))
+        );
+
+        // only notation is taken in notation lessons, even if the text is right
+        let opts = LessonOptions {
+            notation: true,
+            ..Default::default()
+        };
+        assert_eq!(read_reply("cq k", &ans, &opts), None);
+        assert_eq!(read_reply("||CQ K||", &ans, &opts), None);
+        let (s, correct, marks) = read_reply("-.-. --.- / -.-", &ans, &opts).unwrap();
+        assert_eq!(s, "CQ K");
+        assert!(correct);
+        assert!(!marks.unwrap().contains('❌'));
+
+        // kana answers are graded by their codes, though decoded in the default table
+        let ans = "イロ".to_string();
+        let (_, correct, marks) = read_reply(".- .-.-", &ans, &opts).unwrap();
+        assert!(correct);
+        assert!(!marks.unwrap().contains('❌'));
+    }
+}
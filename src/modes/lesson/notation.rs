@@ -0,0 +1,109 @@
+use crate::morse::{CodeTable, EncodeOptions, Encoder, MorseChar};
+
+// the answer as expected in dot/dash notation, each code with the text it stands for
+pub fn expected(ans: &str, table: CodeTable) -> Vec<(MorseChar, String)> {
+    Encoder::new(&EncodeOptions {
+        wabun_markers: false,
+        table,
+    })
+    .encode_labeled(ans)
+}
+
+/*
+    marks the reply character by character, one line each
+    characters are compared by position, so a missing one shifts the rest
+*/
+pub fn mark(expected: &[(MorseChar, String)], sent: &[MorseChar]) -> String {
+    let name = |c: MorseChar, label: &str| match c {
+        MorseChar::Space => "/".to_string(),
+        _ => label.to_string(),
+    };
+
+    let mut lines = Vec::new();
+    for i in 0..expected.len().max(sent.len()) {
+        let line = match (expected.get(i), sent.get(i)) {
+            (Some((e, l)), Some(s)) if e.code() == s.code() => {
+                format!("⭕ {} `{}`", name(*e, l), e)
+            }
+            (Some((e, l)), Some(s)) => format!("❌ {} `{}` (sent `{}`)", name(*e, l), e, s),
+            (Some((e, l)), None) => format!("❌ {} `{}` (missing)", name(*e, l), e),
+            (None, Some(s)) => format!("❌ (extra `{}`)", s),
+            (None, None) => unreachable!(),
+        };
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/*
+    the reply decoded, marked against the answer, and whether all the codes match
+    graded by the codes, as the answer may be encoded from another table than the one decoded with
+    None unless it is in notation
+*/
+pub fn read_reply(reply: &str, ans: &str, table: CodeTable) -> Option<(String, String, bool)> {
+    let sent = crate::morse::parse_morse_str(reply).ok()?;
+    if sent.is_empty() {
+        return None;
+    }
+    let expected = expected(ans, table);
+    let decoded = crate::morse::decode_as(&sent, &expected, table);
+    let correct = expected.len() == sent.len()
+        && expected
+            .iter()
+            .zip(&sent)
+            .all(|((e, _), s)| e.code() == s.code());
+    let marks = mark(&expected, &sent);
+    Some((decoded.trim().to_uppercase(), marks, correct))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morse::parse_morse_str;
+
+    #[test]
+    fn test_mark() {
+        let table = CodeTable::International;
+        let e = expected("CQ K", table);
+
+        let sent = parse_morse_str("-.-. --.- / -.-").unwrap();
+        assert!(e.iter().map(|(m, _)| *m).eq(sent.iter().copied()));
+        assert!(!mark(&e, &sent).contains('❌'));
+
+        let sent = parse_morse_str("-.-. --.-- / -.- .").unwrap();
+        assert_eq!(
+            mark(&e, &sent),
+            [
+                "⭕ C `-.-.`",
+                "❌ Q `--.-` (sent `--.--`)",
+                "⭕ / `/`",
+                "⭕ K `-.-`",
+                "❌ (extra `.`)",
+            ]
+            .join("\n")
+        );
+
+        let sent = parse_morse_str("-.-.").unwrap();
+        assert!(mark(&e, &sent).ends_with("❌ K `-.-` (missing)"));
+
+        let (s, _, correct) = read_reply("-.-. --.-  /  -.-", "CQ K", table).unwrap();
+        assert_eq!(s, "CQ K");
+        assert!(correct);
+        assert_eq!(read_reply("cq k", "CQ K", table), None);
+    }
+
+    #[test]
+    fn test_mark_kana() {
+        // kana answers in a lesson on the default table fall back to wabun codes
+        let table = CodeTable::International;
+        let (s, marks, correct) = read_reply(".- .-.- -...", "イロハ", table).unwrap();
+        assert_eq!(s, "イロハ");
+        assert!(correct);
+        assert!(!marks.contains('❌'), "{marks}");
+        assert!(marks.starts_with("⭕ イ `.-`"), "{marks}");
+
+        let (_, marks, correct) = read_reply(".- .-.- -..", "イロハ", table).unwrap();
+        assert!(!correct);
+        assert!(marks.ends_with("`-...` (sent `-..`)"), "{marks}");
+    }
+}
@@ -1,2 +1,4 @@
 pub mod lesson;
 pub mod normal;
+pub mod sending;
+pub mod skimmer;
@@ -1,33 +1,179 @@
 use anyhow::Context as _;
 use serenity::model::channel::Message;
+use serenity::model::prelude::{GuildId, UserId};
 use serenity::prelude::Context;
 use songbird::constants::SAMPLE_RATE_RAW;
+use songbird::tracks::TrackHandle;
 use sqlx::Row;
 
-pub async fn on_message(ctx: &Context, msg: &Message, db: &sqlx::SqlitePool) -> anyhow::Result<()> {
+// per-user settings in cw_speed
+pub struct UserConfig {
+    pub speed: f32,
+    pub effective_speed: Option<f32>,
+    pub weight: f32,
+    pub dash_ratio: f32,
+    pub reference: crate::morse::ReferenceWord,
+    pub freq: f32,
+    pub table: crate::morse::CodeTable,
+    pub wabun_markers: bool, // ホレ / ラタ between kana and letters
+    pub fist: crate::morse::Fist,
+    pub tone: crate::cw_audio::Tone,
+    pub volume: f32,
+    pub pan: Option<f32>, // None for mono
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            speed: 20.0,
+            effective_speed: None,
+            weight: 0.5,
+            dash_ratio: 3.0,
+            reference: Default::default(),
+            freq: 800.0,
+            table: Default::default(),
+            wabun_markers: true,
+            fist: Default::default(),
+            tone: Default::default(),
+            volume: 1.0,
+            pan: None,
+        }
+    }
+}
+
+impl UserConfig {
+    pub async fn load(db: &sqlx::SqlitePool, id: UserId) -> anyhow::Result<Self> {
+        let speed_cfgs = sqlx::query("select * from cw_speed where id = ?")
+            .bind(id.to_string())
+            .fetch_all(db)
+            .await?;
+
+        Ok(speed_cfgs
+            .first()
+            .map(|row| Self {
+                speed: row.get("speed"),
+                effective_speed: row.get("effective_speed"),
+                weight: row.get("weight"),
+                dash_ratio: row.get("dash_ratio"),
+                reference: row
+                    .get::<String, _>("reference_word")
+                    .parse()
+                    .unwrap_or_default(),
+                freq: row.get("freq"),
+                table: row
+                    .get::<String, _>("code_table")
+                    .parse()
+                    .unwrap_or_default(),
+                wabun_markers: row.get("wabun_markers"),
+                fist: row.get::<String, _>("fist").parse().unwrap_or_default(),
+                tone: crate::cw_audio::Tone {
+                    rise_ms: row.get("rise_ms"),
+                    envelope: row.get::<String, _>("envelope").parse().unwrap_or_default(),
+                    waveform: row.get::<String, _>("waveform").parse().unwrap_or_default(),
+                },
+                volume: row.get("volume"),
+                pan: row.get("pan"),
+            })
+            .unwrap_or_default())
+    }
+
+    pub fn timing(&self) -> crate::morse::Timing {
+        crate::morse::Timing {
+            effective_wpm: self.effective_speed,
+            weight: self.weight,
+            dash_ratio: self.dash_ratio,
+            reference: self.reference,
+            ..crate::morse::Timing::new(self.speed)
+        }
+    }
+}
+
+// per-guild settings in guild_config
+pub struct GuildConfig {
+    pub volume: f32,
+    pub queue: bool, // messages wait for the one playing instead of overlapping
+}
+
+impl GuildConfig {
+    pub async fn load(db: &sqlx::SqlitePool, id: GuildId) -> anyhow::Result<Self> {
+        let cfgs = sqlx::query("select * from guild_config where id = ?")
+            .bind(id.to_string())
+            .fetch_all(db)
+            .await?;
+
+        Ok(cfgs
+            .first()
+            .map(|row| Self {
+                volume: row.get("volume"),
+                queue: row.get("queue"),
+            })
+            .unwrap_or(Self {
+                volume: 1.0,
+                queue: false,
+            }))
+    }
+}
+
+pub async fn on_message(
+    ctx: &Context,
+    msg: &Message,
+    db: &sqlx::SqlitePool,
+) -> anyhow::Result<Option<TrackHandle>> {
     let s = &msg.content;
     if s.starts_with(';') {
-        return Ok(());
+        return Ok(None);
     }
 
-    let speed_cfgs = sqlx::query("select * from cw_speed where id = ?")
-        .bind(msg.author.id.to_string())
-        .fetch_all(db)
-        .await?;
+    let gid = msg.guild_id.context("no guild")?;
+    let cfg = UserConfig::load(db, msg.author.id).await?;
+    play(ctx, gid, s, &cfg, db, &msg.author.name).await
+}
+
+/*
+    plays along with anything playing, so a running lesson goes on
+    queued instead if the guild says so; by is shown in /cw-queue
+    the track is returned unless queued, so /cw-stop can stop it
+*/
+pub async fn play(
+    ctx: &Context,
+    gid: GuildId,
+    s: &str,
+    cfg: &UserConfig,
+    db: &sqlx::SqlitePool,
+    by: &str,
+) -> anyhow::Result<Option<TrackHandle>> {
+    let guild_cfg = GuildConfig::load(db, gid).await?;
 
-    let (speed, freq) = speed_cfgs
-        .first()
-        .map(|row| (row.get::<f32, _>("speed"), row.get::<f32, _>("freq")))
-        .unwrap_or((20.0, 800.0));
+    let codes = crate::morse::get_morse_str_with(
+        s.to_string(),
+        &crate::morse::EncodeOptions {
+            wabun_markers: cfg.wabun_markers,
+            table: cfg.table,
+        },
+    );
 
     let man = songbird::get(ctx).await.expect("init songbird").clone();
 
-    let handler = man.get(msg.guild_id.context("no guild")?);
+    let handler = man.get(gid);
     if let Some(handler) = handler {
         let mut handler = handler.lock().await;
-        let source = crate::cw_audio::CWAudioPCM::new(s.to_string(), speed, freq, SAMPLE_RATE_RAW)
-            .to_input();
-        handler.play_source(source);
+        let timeline = crate::morse::Timeline::with_fist(&codes, &cfg.timing(), &cfg.fist);
+        let mut pcm =
+            crate::cw_audio::CWAudioPCM::from_timeline(&timeline, cfg.freq, SAMPLE_RATE_RAW)
+                .with_tone(&cfg.tone)
+                .with_volume(cfg.volume * guild_cfg.volume);
+        if let Some(pan) = cfg.pan {
+            pcm = pcm.with_pan(pan);
+        }
+        let mut source = pcm.to_input();
+        if guild_cfg.queue {
+            source.metadata.title = Some(s.to_string());
+            source.metadata.artist = Some(by.to_string());
+            source.metadata.duration = Some(timeline.duration());
+            handler.enqueue_source(source);
+        } else {
+            return Ok(Some(handler.play_source(source)));
+        }
     }
-    Ok(())
+    Ok(None)
 }
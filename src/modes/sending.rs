@@ -0,0 +1,446 @@
+use anyhow::Context as _;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serenity::http::Http;
+use serenity::model::prelude::{ChannelId, GuildId, UserId};
+use serenity::prelude::{Context, Mentionable};
+
+use crate::modes::lesson::{notation, LessonGen};
+use crate::modes::skimmer::{self, Speaker};
+use crate::morse::{Code, Element, MorseChar};
+
+// how well a text was sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct SendingGrade {
+    pub accuracy: f32, // 0.0 ~ 1.0, by codes
+    pub wpm: Option<f32>,
+    pub dash_ratio: Option<f32>, // dash over dot, 3 in standard timing
+    pub char_gap_ratio: Option<f32>, // char gap over element gap, 3 in standard timing
+    pub spread: Option<f32>,     // how much each kind of element varies, 0 for a machine
+}
+
+impl std::fmt::Display for SendingGrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.0}%", self.accuracy * 100.0)?;
+        if let Some(wpm) = self.wpm {
+            write!(f, " · {:.1} wpm", wpm)?;
+        }
+        if let Some(r) = self.dash_ratio {
+            write!(f, " · dash {:.1}", r)?;
+        }
+        if let Some(r) = self.char_gap_ratio {
+            write!(f, " · char gap {:.1}", r)?;
+        }
+        if let Some(s) = self.spread {
+            write!(f, " · spread ±{:.0}%", s * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+fn mean(v: &[f64]) -> Option<f64> {
+    (!v.is_empty()).then(|| v.iter().sum::<f64>() / v.len() as f64)
+}
+
+// codes without the spaces around them
+fn trim(v: &[MorseChar]) -> Vec<Option<Code>> {
+    let start = v.iter().position(|c| !c.is_space()).unwrap_or(v.len());
+    let end = v
+        .iter()
+        .rposition(|c| !c.is_space())
+        .map_or(start, |i| i + 1);
+    v[start..end].iter().map(|c| c.code()).collect()
+}
+
+/*
+    sent is the decoded codes, elements the decoded marks and gaps with their lengths
+    compared by the codes, as the text may be encoded from another table than the sender's
+*/
+pub fn grade(
+    expected: &[MorseChar],
+    sent: &[MorseChar],
+    elements: &[(Element, f64)],
+) -> SendingGrade {
+    let expected = trim(expected);
+    let sent = trim(sent);
+    let errors = crate::util::edit_distance(&sent, &expected);
+    let accuracy = 1.0 - errors as f32 / expected.len().max(1) as f32;
+
+    let mut lengths = HashMap::<Element, Vec<f64>>::new();
+    for &(e, len) in elements {
+        lengths.entry(e).or_default().push(len);
+    }
+    let kind = |e: Element| lengths.get(&e).map(|v| &v[..]).unwrap_or(&[]);
+    let ratio = |a: Element, b: Element| Some((mean(kind(a))? / mean(kind(b))?) as f32);
+
+    // word gaps are left out, as they are often longer than the rest
+    let (time, units) = elements
+        .iter()
+        .filter(|(e, _)| *e != Element::WordGap)
+        .fold((0.0, 0), |(t, u), (e, len)| (t + len, u + e.units()));
+    let wpm = (time > 0.0).then(|| (1.2 * units as f64 / time) as f32);
+
+    // coefficient of variation of each kind, weighted by the count
+    let (sum, n) = [
+        Element::Dot,
+        Element::Dash,
+        Element::ElementGap,
+        Element::CharGap,
+    ]
+    .iter()
+    .map(|&e| kind(e))
+    .filter(|v| v.len() >= 2)
+    .fold((0.0, 0), |(sum, n), v| {
+        let m = mean(v).unwrap_or_default();
+        let var = v.iter().map(|x| (x - m).powi(2)).sum::<f64>() / v.len() as f64;
+        (sum + var.sqrt() / m * v.len() as f64, n + v.len())
+    });
+    let spread = (n > 0).then(|| (sum / n as f64) as f32);
+
+    SendingGrade {
+        accuracy: accuracy.max(0.0),
+        wpm,
+        dash_ratio: ratio(Element::Dash, Element::Dot),
+        char_gap_ratio: ratio(Element::CharGap, Element::ElementGap),
+        spread,
+    }
+}
+
+struct Attempt {
+    user: Option<UserId>,
+    grade: SendingGrade,
+}
+
+pub struct SendingModeState {
+    gen: LessonGen,
+    current: Option<String>,
+    attempts: Vec<Attempt>,
+    call: Option<(
+        Arc<serenity::prelude::Mutex<songbird::Call>>,
+        skimmer::Listener,
+    )>,
+    previous: Option<Arc<dyn skimmer::Sink>>, // listening again with it at the end
+}
+
+impl SendingModeState {
+    pub fn new(gen: LessonGen) -> Self {
+        Self {
+            gen,
+            current: None,
+            attempts: Vec::new(),
+            call: None,
+            previous: None,
+        }
+    }
+
+    // the next text to send, if any
+    fn advance(&mut self) -> Option<String> {
+        self.current = self.gen.next().map(|a| a.into_str().to_uppercase());
+        self.current.clone()
+    }
+}
+
+fn next_message(text: Option<String>) -> String {
+    match text {
+        Some(t) => format!("send: **{}**", t),
+        None => "no more texts. /cw-end-sending to see the result".to_string(),
+    }
+}
+
+/*
+    grades each transmission against the current text
+    a transmission ends once it is as long as the text, or after a silence
+*/
+struct Grader {
+    http: Arc<Http>,
+    txt_ch: ChannelId,
+    state: Arc<Mutex<SendingModeState>>,
+}
+
+impl Grader {
+    fn grade(&self, speaker: &mut Speaker) {
+        let elements = speaker.take_elements();
+        let chars = speaker.take_chars();
+        if chars.iter().all(|c| c.is_space()) {
+            return;
+        }
+        let Ok(mut st) = self.state.lock() else {
+            log::error!("lock failed");
+            return;
+        };
+        let Some(expected) = st.current.clone() else {
+            return;
+        };
+
+        // read back in the script of the text, e.g. kana sent on the international table
+        let codes = notation::expected(&expected, speaker.table());
+        let sent = crate::morse::decode_as(&chars, &codes, speaker.table());
+        let sent = sent.trim();
+        let codes = codes.into_iter().map(|(c, _)| c).collect::<Vec<_>>();
+        let grade = grade(&codes, &chars, &elements);
+        let user = speaker.user();
+        let who = user
+            .map(|u| u.mention().to_string() + ": ")
+            .unwrap_or_default();
+        let next = next_message(st.advance());
+        st.attempts.push(Attempt {
+            user,
+            grade: grade.clone(),
+        });
+        drop(st);
+
+        skimmer::post(
+            &self.http,
+            self.txt_ch,
+            format!("{}`{}` for `{}` — {}\n{}", who, sent, expected, grade, next),
+        );
+    }
+}
+
+impl skimmer::Sink for Grader {
+    fn packet(&self, speaker: &mut Speaker) {
+        let Some(len) =
+            self.state.lock().ok().and_then(|st| {
+                Some(notation::expected(st.current.as_ref()?, speaker.table()).len())
+            })
+        else {
+            return;
+        };
+        // anything keyed after it goes to the next text
+        let sent = speaker.chars().iter().skip_while(|c| c.is_space()).count();
+        if sent >= len {
+            self.grade(speaker);
+        }
+    }
+
+    fn end(&self, speaker: &mut Speaker) {
+        self.grade(speaker);
+    }
+}
+
+pub async fn start(
+    ctx: &Context,
+    guild: GuildId,
+    txt_ch: ChannelId,
+    listener: skimmer::Listener,
+    state: Arc<Mutex<SendingModeState>>,
+) -> anyhow::Result<()> {
+    let man = songbird::get(ctx).await.expect("init songbird").clone();
+    let call = man.get(guild).context("not in call")?;
+
+    let first = {
+        let mut st = state
+            .lock()
+            .or_else(|_| anyhow::bail!("lock failed"))
+            .context("internal error")?;
+        st.call = Some((call.clone(), listener.clone()));
+        st.advance()
+    };
+    let grader = Grader {
+        http: ctx.http.clone(),
+        txt_ch,
+        state: state.clone(),
+    };
+    // the skimmer, if on, is put back at the end
+    let previous = listener
+        .start(&call, Arc::new(grader))
+        .await?
+        .filter(|s| s.restorable());
+    state
+        .lock()
+        .or_else(|_| anyhow::bail!("lock failed"))
+        .context("internal error")?
+        .previous = previous;
+    skimmer::post(&ctx.http, txt_ch, next_message(first));
+    Ok(())
+}
+
+pub fn end(state: Arc<Mutex<SendingModeState>>) -> anyhow::Result<String> {
+    let mut st = state
+        .lock()
+        .or_else(|_| anyhow::bail!("lock failed"))
+        .context("internal error")?;
+    st.current = None;
+    if let Some((call, listener)) = st.call.take() {
+        let previous = st.previous.take();
+        tokio::spawn(async move {
+            let r = match previous {
+                Some(sink) => listener.start(&call, sink).await,
+                None => listener.stop(&call).await,
+            };
+            if let Err(e) = r {
+                log::error!("{:#}", e);
+            }
+        });
+    }
+
+    if st.attempts.is_empty() {
+        return Ok("bye!".to_owned());
+    }
+
+    let average = |v: &[&SendingGrade], f: fn(&SendingGrade) -> Option<f32>| {
+        let v = v.iter().filter_map(|g| f(g)).collect::<Vec<_>>();
+        (!v.is_empty()).then(|| v.iter().sum::<f32>() / v.len() as f32)
+    };
+    let all = st.attempts.iter().map(|a| &a.grade).collect::<Vec<_>>();
+    let mut result_text = format!(
+        concat! {
+            "# Sending Result\n",
+            "\n",
+            "total attempts: {}\n",
+            "average accuracy: {:.0}%\n",
+            "\n",
+            "(accuracy / speed / dash / spread)\n",
+        },
+        all.len(),
+        average(&all, |g| Some(g.accuracy)).unwrap_or_default() * 100.0,
+    );
+
+    let mut users = HashMap::<Option<UserId>, Vec<&SendingGrade>>::new();
+    for a in &st.attempts {
+        users.entry(a.user).or_default().push(&a.grade);
+    }
+    let mut v = users.into_iter().collect::<Vec<_>>();
+    v.sort_by_key(|(_, g)| std::cmp::Reverse(g.len()));
+
+    for (user, grades) in v {
+        let name = user
+            .map(|u| u.mention().to_string())
+            .unwrap_or_else(|| "someone".to_string());
+        let or_dash = |x: Option<f32>, f: fn(f32) -> String| x.map(f).unwrap_or("-".into());
+        result_text.push_str(&format!(
+            "{}: {:.0}% / {} / {} / {} ({})\n",
+            name,
+            average(&grades, |g| Some(g.accuracy)).unwrap_or_default() * 100.0,
+            or_dash(average(&grades, |g| g.wpm), |x| format!("{:.1} wpm", x)),
+            or_dash(average(&grades, |g| g.dash_ratio), |x| format!("{:.1}", x)),
+            or_dash(average(&grades, |g| g.spread), |x| format!(
+                "±{:.0}%",
+                x * 100.0
+            )),
+            grades.len(),
+        ));
+    }
+
+    result_text.push_str("\nGood job!");
+
+    Ok(result_text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cw_audio::CWAudioPCM;
+    use crate::cw_decode::CWDecoder;
+    use crate::modes::lesson::LessonAnswerBox;
+    use crate::morse::{get_morse_str, CodeTable, Fist, Timeline, Timing};
+    use crate::test_util::packets;
+    use skimmer::Sink;
+    use songbird::constants::SAMPLE_RATE_RAW;
+    use std::str::FromStr;
+
+    // sends the text with the fist and grades what is decoded
+    fn send(text: &str, fist: &Fist) -> SendingGrade {
+        let srate = 8000;
+        let codes = crate::morse::get_morse_str(text.to_string());
+        let timeline = Timeline::with_fist(&codes, &Timing::new(20.0), fist);
+        let samples = CWAudioPCM::from_timeline(&timeline, 600.0, srate).render();
+
+        let mut decoder = CWDecoder::new(srate);
+        let mut chars = decoder.push(&samples);
+        chars.extend(decoder.finish());
+        grade(&codes, &chars, &decoder.take_elements())
+    }
+
+    #[test]
+    fn test_grade() {
+        let text = "CQ DE JA1ABC K";
+        let g = send(text, &Fist::default());
+        assert_eq!(g.accuracy, 1.0);
+        let wpm = g.wpm.unwrap();
+        assert!((wpm - 20.0).abs() < 2.0, "{wpm}");
+        // marks come out a little short and gaps long with the rise time
+        let r = g.dash_ratio.unwrap();
+        assert!((2.5..4.0).contains(&r), "{r}");
+        let r = g.char_gap_ratio.unwrap();
+        assert!((2.0..3.5).contains(&r), "{r}");
+        let machine = g.spread.unwrap();
+        assert!(machine < 0.05, "{machine}");
+
+        let fist = Fist {
+            seed: Some(1),
+            ..Fist::from_str("sloppy").unwrap()
+        };
+        let sloppy = send(text, &fist).spread.unwrap();
+        assert!(sloppy > machine * 2.0, "{sloppy} {machine}");
+
+        let g = grade(
+            &get_morse_str(text.to_string()),
+            &get_morse_str("CQ DE JA1ABD K".to_string()),
+            &[],
+        );
+        assert!((g.accuracy - 13.0 / 14.0).abs() < 1e-6);
+        assert_eq!(g.wpm, None);
+    }
+
+    // keys the text as discord sends it
+    fn key(sink: &dyn Sink, speaker: &mut Speaker, text: &str) {
+        let samples = CWAudioPCM::new(text.to_string(), 20.0, 600.0, SAMPLE_RATE_RAW).render();
+        for (ts, audio) in packets(&samples) {
+            speaker.push(ts, &audio);
+            sink.packet(speaker);
+        }
+    }
+
+    fn grader(text: &str) -> (Grader, Arc<Mutex<SendingModeState>>) {
+        let text = text.to_string();
+        let gen = std::iter::repeat_with(move || Box::new(text.clone()) as LessonAnswerBox);
+        let state = Arc::new(Mutex::new(SendingModeState::new(Box::new(gen))));
+        state.lock().unwrap().advance();
+        let grader = Grader {
+            http: Arc::new(Http::new("")),
+            txt_ch: ChannelId(1),
+            state: state.clone(),
+        };
+        (grader, state)
+    }
+
+    #[tokio::test]
+    async fn test_grader() {
+        let text = "CQ DE JA1ABC K";
+        let (grader, state) = grader(text);
+        let mut sp = Speaker::new(None, CodeTable::International);
+        let accuracies = || {
+            let st = state.lock().unwrap();
+            st.attempts
+                .iter()
+                .map(|a| a.grade.accuracy)
+                .collect::<Vec<_>>()
+        };
+
+        // graded once as a whole when as long as the text; the next one is keyed right after
+        key(&grader, &mut sp, &format!("{text} CQ"));
+        assert_eq!(accuracies(), [1.0]);
+
+        // and the rest is graded when the speaker has been silent for a while
+        sp.finish();
+        grader.end(&mut sp);
+        let acc = accuracies();
+        assert_eq!(acc.len(), 2);
+        assert!(acc[1] < 0.5, "{acc:?}");
+    }
+
+    #[tokio::test]
+    async fn test_grader_kana() {
+        // kana are keyed in wabun codes, whatever the sender's table is
+        let (grader, state) = grader("イロハ");
+        let mut sp = Speaker::new(None, CodeTable::International);
+        key(&grader, &mut sp, "イロハ");
+        sp.finish();
+        grader.end(&mut sp);
+        let st = state.lock().unwrap();
+        assert_eq!(st.attempts.len(), 1);
+        assert_eq!(st.attempts[0].grade.accuracy, 1.0);
+    }
+}
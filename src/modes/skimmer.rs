@@ -0,0 +1,418 @@
+use anyhow::Context as _;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serenity::async_trait;
+use serenity::http::Http;
+use serenity::model::prelude::{ChannelId, UserId};
+use serenity::prelude::Mentionable;
+use songbird::constants::SAMPLE_RATE_RAW;
+use songbird::events::{CoreEvent, Event, EventContext};
+
+use crate::cw_decode::CWDecoder;
+use crate::morse::{CodeTable, DecodeMode, Element, MorseChar};
+
+const DECIMATE: usize = 6; // 48kHz to 8kHz
+const MAX_FILL_SECS: usize = 2; // silence between packets filled at most
+const LINE_CHARS: usize = 40; // posted at a word gap once this long
+const LEAD_IN_SECS: f32 = 0.1; // silence before each transmission, for the levels to settle
+
+// a transmission ends after this long without sound; several word gaps even at slow speeds
+// (discord reports the speaker silent after only ~100 ms, within a character gap)
+const END_SILENCE: std::time::Duration = std::time::Duration::from_secs(3);
+
+/*
+    audio of one speaker (an SSRC) into text
+    discord sends nothing while silent, so gaps are filled from the RTP timestamps
+*/
+pub struct Speaker {
+    user: Option<UserId>,
+    table: CodeTable,
+    decoder: CWDecoder,
+    chars: Vec<MorseChar>,
+    elements: Vec<(Element, f64)>,
+    next_ts: Option<u32>,
+    acc: (f32, usize), // sum and count toward the next decimated sample
+    silent_since: Option<std::time::Instant>,
+}
+
+impl Speaker {
+    pub fn new(user: Option<UserId>, table: CodeTable) -> Self {
+        Self {
+            user,
+            table,
+            decoder: CWDecoder::new(SAMPLE_RATE_RAW / DECIMATE),
+            chars: Vec::new(),
+            elements: Vec::new(),
+            next_ts: None,
+            acc: (0.0, 0),
+            silent_since: None,
+        }
+    }
+
+    pub fn user(&self) -> Option<UserId> {
+        self.user
+    }
+
+    pub fn table(&self) -> CodeTable {
+        self.table
+    }
+
+    // interleaved stereo at 48kHz, timestamp in samples per channel
+    pub fn push(&mut self, ts: u32, audio: &[i16]) {
+        let frames = audio.len() / 2;
+        let gap = match self.next_ts {
+            Some(next) => {
+                let d = ts.wrapping_sub(next) as i32;
+                if d < 0 {
+                    return; // late
+                }
+                (d as usize).min(MAX_FILL_SECS * SAMPLE_RATE_RAW)
+            }
+            // the first packet starts with the sound
+            None => (LEAD_IN_SECS * SAMPLE_RATE_RAW as f32) as usize,
+        };
+        self.push_frames(std::iter::repeat_n(0.0, gap));
+        self.next_ts = Some(ts.wrapping_add(frames as u32));
+
+        self.push_frames(
+            audio
+                .chunks_exact(2)
+                .map(|s| (s[0] as f32 + s[1] as f32) / 2.0 / i16::MAX as f32),
+        );
+    }
+
+    fn push_frames(&mut self, frames: impl Iterator<Item = f32>) {
+        let mut v = Vec::new();
+        for x in frames {
+            self.acc.0 += x;
+            self.acc.1 += 1;
+            if self.acc.1 == DECIMATE {
+                v.push(self.acc.0 / DECIMATE as f32);
+                self.acc = (0.0, 0);
+            }
+        }
+        self.chars.extend(self.decoder.push(&v));
+        self.elements.extend(self.decoder.take_elements());
+    }
+
+    // end of a transmission; the rest is decoded
+    pub fn finish(&mut self) {
+        self.chars.extend(self.decoder.finish());
+        self.elements.extend(self.decoder.take_elements());
+        self.next_ts = None;
+    }
+
+    // the codes decoded so far, without taking them
+    pub fn chars(&self) -> &[MorseChar] {
+        &self.chars
+    }
+
+    // all the codes decoded so far, taken out
+    pub fn take_chars(&mut self) -> Vec<MorseChar> {
+        std::mem::take(&mut self.chars)
+    }
+
+    // marks and gaps so far, with their lengths in seconds
+    pub fn take_elements(&mut self) -> Vec<(Element, f64)> {
+        std::mem::take(&mut self.elements)
+    }
+
+    // text to post; all of it when forced, otherwise a long enough line up to a word gap
+    pub fn take_line(&mut self, force: bool) -> Option<String> {
+        let n = if force {
+            self.chars.len()
+        } else if self.chars.len() >= LINE_CHARS {
+            self.chars.iter().rposition(|c| c.is_space())?
+        } else {
+            return None;
+        };
+        let chars = self.chars.drain(..n).collect::<Vec<_>>();
+        let s = crate::morse::decode_to_string(&chars, DecodeMode::Table(self.table));
+        let s = s.trim();
+        (!s.is_empty()).then(|| s.to_string())
+    }
+}
+
+// what is done with the decoded audio
+pub trait Sink: Send + Sync {
+    // after each packet
+    fn packet(&self, speaker: &mut Speaker);
+    // when the speaker has been silent for a while, after the rest is decoded
+    fn end(&self, speaker: &mut Speaker);
+    // whether to put it back when a mode that listened in its place ends
+    fn restorable(&self) -> bool {
+        false
+    }
+}
+
+pub fn post(http: &Arc<Http>, ch: ChannelId, content: String) {
+    let http = http.clone();
+    tokio::spawn(async move {
+        if let Err(e) = ch
+            .send_message(&http, |m| {
+                m.content(content).allowed_mentions(|a| a.empty_parse())
+            })
+            .await
+        {
+            log::error!("post failed: {}", e);
+        }
+    });
+}
+
+// posts what is sent in CW, per user
+pub struct Post {
+    http: Arc<Http>,
+    txt_ch: ChannelId,
+}
+
+impl Post {
+    pub fn new(http: Arc<Http>, txt_ch: ChannelId) -> Self {
+        Self { http, txt_ch }
+    }
+
+    fn post(&self, user: Option<UserId>, text: String) {
+        let content = match user {
+            Some(u) => format!("{}: {}", u.mention(), text),
+            None => text,
+        };
+        post(&self.http, self.txt_ch, content);
+    }
+}
+
+impl Sink for Post {
+    fn packet(&self, speaker: &mut Speaker) {
+        speaker.take_elements();
+        if let Some(s) = speaker.take_line(false) {
+            self.post(speaker.user(), s);
+        }
+    }
+
+    fn end(&self, speaker: &mut Speaker) {
+        speaker.take_elements();
+        if let Some(s) = speaker.take_line(true) {
+            self.post(speaker.user(), s);
+        }
+    }
+
+    fn restorable(&self) -> bool {
+        true
+    }
+}
+
+/*
+    receives the voice of a call and decodes each speaker into the sink, if any
+    one per call, registered when joining; listening starts and stops by swapping the sink,
+    so that no other handler on the call is touched
+*/
+#[derive(Clone)]
+pub struct Listener {
+    db: sqlx::SqlitePool,
+    speakers: Arc<Mutex<HashMap<u32, Speaker>>>,
+    sink: Arc<Mutex<Option<Arc<dyn Sink>>>>,
+}
+
+impl Listener {
+    pub fn new(db: sqlx::SqlitePool) -> Self {
+        Self {
+            db,
+            speakers: Arc::new(Mutex::new(HashMap::new())),
+            sink: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub async fn register(&self, call: &Arc<serenity::prelude::Mutex<songbird::Call>>) {
+        let mut handler = call.lock().await;
+        for e in [
+            CoreEvent::SpeakingStateUpdate,
+            CoreEvent::SpeakingUpdate,
+            CoreEvent::VoicePacket,
+            CoreEvent::ClientDisconnect,
+        ] {
+            handler.add_global_event(e.into(), self.clone());
+        }
+    }
+
+    // None when not listening
+    pub fn sink(&self) -> Option<Arc<dyn Sink>> {
+        self.sink.lock().ok()?.clone()
+    }
+
+    // returns the previous sink; what was being decoded for it is dropped
+    fn swap_sink(&self, sink: Option<Arc<dyn Sink>>) -> anyhow::Result<Option<Arc<dyn Sink>>> {
+        let mut speakers = self
+            .speakers
+            .lock()
+            .or_else(|_| anyhow::bail!("lock failed"))
+            .context("internal error")?;
+        speakers
+            .values_mut()
+            .for_each(|sp| *sp = Speaker::new(sp.user, sp.table));
+        let mut cur = self
+            .sink
+            .lock()
+            .or_else(|_| anyhow::bail!("lock failed"))
+            .context("internal error")?;
+        Ok(std::mem::replace(&mut *cur, sink))
+    }
+
+    // starts listening, or replaces the sink if already listening; returns the previous sink
+    pub async fn start(
+        &self,
+        call: &Arc<serenity::prelude::Mutex<songbird::Call>>,
+        sink: Arc<dyn Sink>,
+    ) -> anyhow::Result<Option<Arc<dyn Sink>>> {
+        let previous = self.swap_sink(Some(sink))?;
+
+        let mut handler = call.lock().await;
+        let config = handler
+            .config()
+            .clone()
+            .decode_mode(songbird::driver::DecodeMode::Decode);
+        handler.set_config(config);
+        handler.deafen(false).await.context("undeafen failed")?;
+        Ok(previous)
+    }
+
+    // returns the sink that was in use
+    pub async fn stop(
+        &self,
+        call: &Arc<serenity::prelude::Mutex<songbird::Call>>,
+    ) -> anyhow::Result<Option<Arc<dyn Sink>>> {
+        let previous = self.swap_sink(None)?;
+
+        let mut handler = call.lock().await;
+        let config = handler
+            .config()
+            .clone()
+            .decode_mode(songbird::driver::DecodeMode::Decrypt);
+        handler.set_config(config);
+        handler.deafen(true).await.context("deafen failed")?;
+        Ok(previous)
+    }
+
+    async fn on_event(&self, ctx: &EventContext<'_>) -> anyhow::Result<()> {
+        // speakers are known even while not listening, to tell who is sending later
+        if let EventContext::SpeakingStateUpdate(s) = ctx {
+            let user = s.user_id.map(|u| UserId(u.0));
+            let table = match user {
+                Some(u) => {
+                    crate::modes::normal::UserConfig::load(&self.db, u)
+                        .await?
+                        .table
+                }
+                None => CodeTable::default(),
+            };
+            let mut speakers = self
+                .speakers
+                .lock()
+                .or_else(|_| anyhow::bail!("lock failed"))
+                .context("internal error")?;
+            let sp = speakers
+                .entry(s.ssrc)
+                .or_insert_with(|| Speaker::new(user, table));
+            sp.user = user;
+            sp.table = table;
+            return Ok(());
+        }
+
+        let sink = self.sink();
+        let mut speakers = self
+            .speakers
+            .lock()
+            .or_else(|_| anyhow::bail!("lock failed"))
+            .context("internal error")?;
+
+        match ctx {
+            EventContext::VoicePacket(data) => {
+                let (Some(sink), Some(audio)) = (sink, data.audio) else {
+                    return Ok(());
+                };
+                let sp = speakers
+                    .entry(data.packet.ssrc)
+                    .or_insert_with(|| Speaker::new(None, CodeTable::default()));
+                sp.silent_since = None;
+                sp.push(data.packet.timestamp.0 .0, audio);
+                sink.packet(sp);
+            }
+            EventContext::SpeakingUpdate(data) if !data.speaking => {
+                if let Some(sp) = speakers.get_mut(&data.ssrc) {
+                    sp.silent_since = Some(std::time::Instant::now());
+                    let listener = self.clone();
+                    let ssrc = data.ssrc;
+                    tokio::spawn(async move {
+                        tokio::time::sleep(END_SILENCE).await;
+                        listener.end_if_silent(ssrc);
+                    });
+                }
+            }
+            EventContext::ClientDisconnect(d) => {
+                // what they sent last is not lost
+                let user = Some(UserId(d.user_id.0));
+                for sp in speakers.values_mut().filter(|sp| sp.user == user) {
+                    if let Some(sink) = &sink {
+                        sp.finish();
+                        sink.end(sp);
+                    }
+                }
+                speakers.retain(|_, sp| sp.user != user);
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    // unless the speaker has been heard again since
+    fn end_if_silent(&self, ssrc: u32) {
+        let Some(sink) = self.sink() else {
+            return;
+        };
+        let Ok(mut speakers) = self.speakers.lock() else {
+            log::error!("lock failed");
+            return;
+        };
+        let Some(sp) = speakers.get_mut(&ssrc) else {
+            return;
+        };
+        if sp.silent_since.is_some_and(|t| t.elapsed() >= END_SILENCE) {
+            sp.silent_since = None;
+            sp.finish();
+            sink.end(sp);
+        }
+    }
+}
+
+#[async_trait]
+impl songbird::events::EventHandler for Listener {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        if let Err(e) = self.on_event(ctx).await {
+            log::error!("{:#}", e);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cw_audio::CWAudioPCM;
+    use crate::test_util::packets;
+
+    #[test]
+    fn test_speaker() {
+        let text = "CQ CQ CQ DE JA1ABC JA1ABC JA1ABC JA1ABC PSE K";
+        let samples = CWAudioPCM::new(text.to_string(), 25.0, 700.0, SAMPLE_RATE_RAW).render();
+
+        let mut sp = Speaker::new(None, CodeTable::International);
+        let mut lines = Vec::new();
+        for (ts, audio) in packets(&samples) {
+            sp.push(ts, &audio);
+            lines.extend(sp.take_line(false));
+        }
+        sp.finish();
+        lines.extend(sp.take_line(true));
+        // a long line is posted before the end
+        assert_eq!(lines.len(), 2, "{lines:?}");
+        assert_eq!(lines.join(" "), text);
+    }
+}
@@ -0,0 +1,190 @@
+use std::str::FromStr;
+
+/// dot/dash sequence of a character, up to 16 elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Code {
+    len: u8,
+    bits: u16, // 0 = dot, 1 = dash; MSB first
+}
+
+impl Code {
+    pub const fn new(len: u8, bits: u16) -> Self {
+        Self { len, bits }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn bits(&self) -> u16 {
+        self.bits
+    }
+
+    // dots and dashes, without gaps
+    pub fn marks(&self) -> impl Iterator<Item = Element> {
+        let Self { len, bits } = *self;
+        (0..len).rev().map(move |i| {
+            if bits & (1 << i) != 0 {
+                Element::Dash
+            } else {
+                Element::Dot
+            }
+        })
+    }
+
+    // dots and dashes with element gaps in between
+    pub fn elements(&self) -> impl Iterator<Item = Element> {
+        self.marks().enumerate().flat_map(|(i, e)| {
+            (i > 0)
+                .then_some(Element::ElementGap)
+                .into_iter()
+                .chain([e])
+        })
+    }
+
+    // keyed without a gap, e.g. A + R for <AR>
+    pub fn concat(&self, other: Code) -> Option<Code> {
+        if self.len() + other.len() > 16 {
+            return None;
+        }
+        // shifting by 16 leaves nothing of self, which is empty then
+        let bits = self.bits.checked_shl(other.len as u32).unwrap_or(0);
+        Some(Code::new(self.len + other.len, bits | other.bits))
+    }
+}
+
+impl From<(u8, u16)> for Code {
+    fn from((len, bits): (u8, u16)) -> Self {
+        Code::new(len, bits)
+    }
+}
+
+impl From<Code> for (u8, u16) {
+    fn from(c: Code) -> Self {
+        (c.len, c.bits)
+    }
+}
+
+impl std::fmt::Display for Code {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.marks().try_for_each(|e| write!(f, "{}", e))
+    }
+}
+
+impl FromStr for Code {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        anyhow::ensure!(!s.is_empty(), "empty code");
+        anyhow::ensure!(s.chars().count() <= 16, "code too long: {}", s);
+        let mut b = 0u16;
+        for e in s.chars() {
+            b = (b << 1)
+                | match e {
+                    '.' | '・' => 0,
+                    '-' | '_' | '－' => 1,
+                    _ => anyhow::bail!("invalid character: {}", e),
+                };
+        }
+        Ok(Code::new(s.chars().count() as u8, b))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MorseChar {
+    Char(Code),
+    // characters keyed together without a gap, like <AR>
+    Prosign(Code),
+    Space,
+}
+
+impl MorseChar {
+    pub fn code(&self) -> Option<Code> {
+        match self {
+            MorseChar::Char(c) | MorseChar::Prosign(c) => Some(*c),
+            MorseChar::Space => None,
+        }
+    }
+
+    pub fn is_space(&self) -> bool {
+        *self == MorseChar::Space
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = Element> {
+        let (code, space) = match self.code() {
+            Some(c) => (Some(c), None),
+            None => (None, Some(Element::WordGap)),
+        };
+        code.into_iter().flat_map(|c| c.elements()).chain(space)
+    }
+}
+
+impl From<Code> for MorseChar {
+    fn from(c: Code) -> Self {
+        MorseChar::Char(c)
+    }
+}
+
+impl std::fmt::Display for MorseChar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MorseChar::Char(c) | MorseChar::Prosign(c) => write!(f, "{}", c),
+            MorseChar::Space => write!(f, "/"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Element {
+    Dot,
+    Dash,
+    ElementGap, // between dots and dashes of a character
+    CharGap,
+    WordGap,
+}
+
+impl Element {
+    pub fn is_mark(&self) -> bool {
+        matches!(self, Element::Dot | Element::Dash)
+    }
+
+    // length in dots, in standard timing
+    pub fn units(&self) -> usize {
+        match self {
+            Element::Dot | Element::ElementGap => 1,
+            Element::Dash | Element::CharGap => 3,
+            Element::WordGap => 7,
+        }
+    }
+}
+
+impl std::fmt::Display for Element {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Element::Dot => write!(f, "."),
+            Element::Dash => write!(f, "-"),
+            Element::ElementGap => Ok(()),
+            Element::CharGap => write!(f, " "),
+            Element::WordGap => write!(f, " / "),
+        }
+    }
+}
+
+// all elements of the characters, with char gaps between them
+pub fn elements(chars: &[MorseChar]) -> impl Iterator<Item = Element> + '_ {
+    chars.iter().enumerate().flat_map(move |(i, c)| {
+        let gap = i > 0 && !c.is_space() && !chars[i - 1].is_space();
+        gap.then_some(Element::CharGap)
+            .into_iter()
+            .chain(c.elements())
+    })
+}
+
+// dot/dash notation like ".- -... / -.-."
+pub fn to_notation(chars: &[MorseChar]) -> String {
+    elements(chars).map(|e| e.to_string()).collect::<String>()
+}
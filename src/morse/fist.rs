@@ -0,0 +1,164 @@
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+
+use super::{Element, Timing};
+
+/// sender profile, to make the timing sound like a human operator
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Fist {
+    // standard deviation of each element length, in dots
+    pub jitter: f32,
+    // overrides Timing::weight
+    pub weight: Option<f32>,
+    // semi-automatic key: dots are short and even, dashes are long
+    pub bug: bool,
+    // random stretch of gaps between characters and words, as a ratio
+    pub spacing: f32,
+    pub seed: Option<u64>,
+}
+
+impl Fist {
+    pub const PRESETS: [&'static str; 6] =
+        ["machine", "straight", "heavy", "light", "bug", "sloppy"];
+
+    pub fn is_machine(&self) -> bool {
+        *self
+            == Fist {
+                seed: self.seed,
+                ..Default::default()
+            }
+    }
+
+    pub fn timing(&self, timing: &Timing) -> Timing {
+        Timing {
+            weight: self.weight.unwrap_or(timing.weight),
+            ..timing.clone()
+        }
+    }
+}
+
+impl FromStr for Fist {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let f = Fist::default();
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "machine" => f,
+            "straight" => Fist {
+                jitter: 0.15,
+                spacing: 0.3,
+                ..f
+            },
+            "heavy" => Fist {
+                jitter: 0.1,
+                weight: Some(0.62),
+                spacing: 0.2,
+                ..f
+            },
+            "light" => Fist {
+                jitter: 0.1,
+                weight: Some(0.4),
+                spacing: 0.2,
+                ..f
+            },
+            "bug" => Fist {
+                jitter: 0.1,
+                bug: true,
+                spacing: 0.4,
+                ..f
+            },
+            "sloppy" => Fist {
+                jitter: 0.3,
+                spacing: 0.7,
+                ..f
+            },
+            _ => anyhow::bail!(
+                "unknown fist: {}\navailable selections are: {}",
+                s,
+                Fist::PRESETS.join(", ")
+            ),
+        })
+    }
+}
+
+// applies a fist to element lengths one by one
+pub(super) struct Hand {
+    fist: Fist,
+    rng: StdRng,
+}
+
+impl Hand {
+    pub fn new(fist: &Fist) -> Self {
+        Self {
+            fist: fist.clone(),
+            rng: crate::util::rng(fist.seed),
+        }
+    }
+
+    // length in seconds of e, whose exact length is secs and dot is the length of a dot
+    pub fn element_secs(&mut self, e: Element, secs: f32, dot: f32) -> f32 {
+        if self.fist.is_machine() {
+            return secs;
+        }
+
+        let secs = match e {
+            // made by the vibroplex, not by hand
+            Element::Dot if self.fist.bug => return secs * 0.85,
+            Element::ElementGap if self.fist.bug => return secs * 1.15,
+            Element::Dash if self.fist.bug => secs * 1.25,
+            Element::CharGap | Element::WordGap => {
+                secs * (1.0 + self.fist.spacing * crate::util::gauss(&mut self.rng).abs())
+            }
+            _ => secs,
+        };
+
+        (secs + self.fist.jitter * dot * crate::util::gauss(&mut self.rng)).max(dot * 0.3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morse::{get_morse_str, Timeline};
+
+    #[test]
+    fn test_fist_seeded() {
+        let codes = get_morse_str("CQ CQ DE JA1ABC".to_string());
+        let timing = Timing::new(20.0);
+        let machine = Timeline::new(&codes, &timing);
+
+        for p in Fist::PRESETS {
+            let fist = Fist {
+                seed: Some(42),
+                ..p.parse().unwrap()
+            };
+            let a = Timeline::with_fist(&codes, &timing, &fist);
+            let b = Timeline::with_fist(&codes, &timing, &fist);
+            assert_eq!(a, b);
+            assert_eq!(a.events().len(), machine.events().len());
+            assert_eq!(a == machine, p == "machine", "{p}");
+        }
+    }
+
+    #[test]
+    fn test_fist_bug() {
+        let codes = get_morse_str("5".to_string()); // .....
+        let timing = Timing::new(20.0);
+        let fist = Fist {
+            bug: true,
+            jitter: 0.2,
+            ..Default::default()
+        };
+        let marks = Timeline::with_fist(&codes, &timing, &fist)
+            .events()
+            .iter()
+            .filter(|e| e.1)
+            .map(|e| e.0)
+            .collect::<Vec<_>>();
+        // dots of a bug are all the same
+        assert_eq!(marks.len(), 5);
+        assert!(marks.iter().all(|&m| m == marks[0]));
+        assert!(marks[0] < timing.dot().as_secs_f64());
+    }
+}
@@ -0,0 +1,674 @@
+use kanaria::string::UCSStr;
+use unicode_normalization::UnicodeNormalization;
+
+mod code;
+mod fist;
+mod table;
+mod timeline;
+mod timing;
+
+pub use code::{elements, to_notation, Code, Element, MorseChar};
+pub use fist::Fist;
+pub use table::CodeTable;
+pub use timeline::{Keyer, Timeline};
+pub use timing::{ReferenceWord, Timing};
+
+// looks up the international and wabun tables
+pub fn get_morse(c: char) -> Option<Code> {
+    CodeTable::International
+        .get(c)
+        .or_else(|| CodeTable::Wabun.get(c))
+}
+
+#[derive(Debug, Clone)]
+pub struct EncodeOptions {
+    // insert ホレ / ラタ when switching between kana and latin characters
+    pub wabun_markers: bool,
+    // looked up first; other tables are used for characters not in it
+    pub table: CodeTable,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            wabun_markers: true,
+            table: CodeTable::default(),
+        }
+    }
+}
+
+// ホレ (-..---) and ラタ (...-.)
+pub const WABUN_START: Code = Code::new(6, 0b100111);
+pub const WABUN_END: Code = Code::new(5, 0b00010);
+
+pub fn get_morse_str(s: String) -> Vec<MorseChar> {
+    get_morse_str_with(s, &EncodeOptions::default())
+}
+
+pub fn get_morse_str_with(s: String, opts: &EncodeOptions) -> Vec<MorseChar> {
+    Encoder::new(opts).encode(&s)
+}
+
+/// encodes text given in pieces as if it were given at once
+/// wabun markers and spacing carry over from the previous piece; pieces should be cut at whitespace
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    opts: EncodeOptions,
+    wabun: Option<bool>, // script of the last character; None at the beginning
+    last: Option<MorseChar>,
+}
+
+impl Encoder {
+    pub fn new(opts: &EncodeOptions) -> Self {
+        Self {
+            opts: opts.clone(),
+            wabun: None,
+            last: None,
+        }
+    }
+
+    pub fn encode(&mut self, s: &str) -> Vec<MorseChar> {
+        self.encode_labeled(s).into_iter().map(|(m, _)| m).collect()
+    }
+
+    /// same as encode, each code paired with the (normalized) text it was encoded from
+    /// codes expanded from one character share its label
+    pub fn encode_labeled(&mut self, s: &str) -> Vec<(MorseChar, String)> {
+        let s = UCSStr::from_str(s).upper_case().katakana().to_string();
+
+        let s = s.nfkc().collect::<String>();
+
+        let opts = &self.opts;
+        let chars = s.chars().collect::<Vec<_>>();
+        let mut v = Vec::<(MorseChar, String)>::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let start = i;
+            let digraph = opts
+                .table
+                .digraphs()
+                .iter()
+                .find(|(d, _)| chars[i..].iter().take(d.len()).copied().eq(d.chars()));
+            let (m, is_wabun) = if chars[i] == '<' {
+                match get_prosign(&chars[i + 1..]) {
+                    Some((m, n)) => {
+                        i += n + 2;
+                        (vec![MorseChar::Prosign(m)], Some(false))
+                    }
+                    None => {
+                        i += 1;
+                        (vec![MorseChar::Space], None)
+                    }
+                }
+            } else if let Some((d, m)) = digraph {
+                i += d.len();
+                (vec![MorseChar::Char(*m)], Some(false))
+            } else {
+                i += 1;
+                (
+                    encode_char(chars[i - 1], opts.table).unwrap_or(vec![MorseChar::Space]),
+                    get_script(chars[i - 1]),
+                )
+            };
+
+            let label = chars[start..i].iter().collect::<String>();
+            let mut push = |m: MorseChar, label: &str| {
+                if m.is_space() && self.last.map(|x| x.is_space()).unwrap_or(false) {
+                    return;
+                }
+                v.push((m, label.to_string()));
+                self.last = Some(m);
+            };
+
+            if opts.wabun_markers
+                && is_wabun.is_some()
+                && self.wabun.is_some()
+                && self.wabun != is_wabun
+            {
+                let (marker, name) = if is_wabun == Some(true) {
+                    (WABUN_START, "ホレ")
+                } else {
+                    (WABUN_END, "ラタ")
+                };
+                push(MorseChar::Space, " ");
+                push(MorseChar::Char(marker), name);
+                push(MorseChar::Space, " ");
+            }
+            if is_wabun.is_some() {
+                self.wabun = is_wabun;
+            }
+
+            m.into_iter().for_each(|m| push(m, &label));
+        }
+        v
+    }
+}
+
+/*
+    looks up the given table first, then the others
+    characters not in any table are decomposed (e.g. ガ into カ and dakuten, Ё into Е)
+    combining marks not in the tables are dropped
+*/
+fn encode_char(c: char, table: CodeTable) -> Option<Vec<MorseChar>> {
+    let tables = std::iter::once(table).chain(CodeTable::ALL);
+    for t in tables.clone() {
+        if let Some(m) = t.get(c) {
+            return Some(vec![m.into()]);
+        }
+        if let Some(v) = t.get_expanded(c) {
+            return Some(v.into_iter().map(MorseChar::from).collect());
+        }
+    }
+
+    let d = std::iter::once(c).nfd().collect::<Vec<_>>();
+    if d.len() < 2 {
+        return None;
+    }
+    let v = d
+        .into_iter()
+        .filter_map(|c| {
+            let m = tables.clone().find_map(|t| t.get(c));
+            if m.is_none() && !unicode_normalization::char::is_combining_mark(c) {
+                return Some(MorseChar::Space);
+            }
+            m.map(MorseChar::from)
+        })
+        .collect();
+    Some(v)
+}
+
+// Some(true) for kana, Some(false) for letters, None for digits, symbols, etc.
+fn get_script(c: char) -> Option<bool> {
+    let c = std::iter::once(c).nfd().next().unwrap_or(c);
+    if CodeTable::Wabun.get(c).is_some() && CodeTable::International.get(c).is_none() {
+        Some(true)
+    } else if c.is_alphabetic() {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/*
+    merges characters until '>' into one code (e.g. "AR>" for <AR>)
+    returns the code and the number of characters inside the brackets
+*/
+fn get_prosign(s: &[char]) -> Option<(Code, usize)> {
+    let n = s.iter().position(|&c| c == '>')?;
+    if n == 0 {
+        return None;
+    }
+    let mut r = Code::new(0, 0);
+    for &c in &s[..n] {
+        r = r.concat(get_morse(c)?)?;
+    }
+    Some((r, n))
+}
+
+// well-known prosigns, used to name codes without a character on decoding
+pub const PROSIGNS: &[&str] = &["AR", "AS", "BT", "CT", "HH", "KN", "SK", "SN", "SOS"];
+
+/// which table(s) to use on decoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeMode {
+    Table(CodeTable),
+    // try both tables; codes valid in both are reported as ambiguous
+    Auto,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decoded {
+    Char(char),
+    Ambiguous { international: char, wabun: char },
+    Prosign(&'static str),
+    Digraph(&'static str),
+    // ホレ / ラタ recognized in DecodeMode::Auto
+    Switch(CodeTable),
+    Space,
+    Unknown(Code),
+}
+
+impl std::fmt::Display for Decoded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Decoded::Char(c) => write!(f, "{}", c),
+            Decoded::Ambiguous {
+                international,
+                wabun,
+            } => write!(f, "[{}|{}]", international, wabun),
+            Decoded::Prosign(p) => write!(f, "<{}>", p),
+            Decoded::Digraph(s) => write!(f, "{}", s),
+            Decoded::Switch(_) => Ok(()),
+            Decoded::Space => write!(f, " "),
+            Decoded::Unknown(_) => write!(f, "*"),
+        }
+    }
+}
+
+pub fn decode_char(c: MorseChar, mode: DecodeMode) -> Decoded {
+    let code = match c {
+        MorseChar::Space => return Decoded::Space,
+        MorseChar::Prosign(code) => {
+            if let Some(p) = get_prosign_name(code) {
+                return Decoded::Prosign(p);
+            }
+            code
+        }
+        MorseChar::Char(code) => code,
+    };
+    let r = match mode {
+        DecodeMode::Table(t) => t
+            .lookup_digraph(code)
+            .map(Decoded::Digraph)
+            .or_else(|| t.lookup(code).map(Decoded::Char)),
+        DecodeMode::Auto => {
+            match (
+                CodeTable::International.lookup(code),
+                CodeTable::Wabun.lookup(code),
+            ) {
+                (Some(a), Some(b)) if a == b => Some(Decoded::Char(a)),
+                (Some(a), Some(b)) => Some(Decoded::Ambiguous {
+                    international: a,
+                    wabun: b,
+                }),
+                (a, b) => a.or(b).map(Decoded::Char),
+            }
+        }
+    };
+    r.or_else(|| get_prosign_name(code).map(Decoded::Prosign))
+        .unwrap_or(Decoded::Unknown(code))
+}
+
+fn get_prosign_name(code: Code) -> Option<&'static str> {
+    PROSIGNS
+        .iter()
+        .copied()
+        .find(|p| get_prosign(&p.chars().chain(['>']).collect::<Vec<_>>()) == Some((code, p.len())))
+}
+
+/*
+    in DecodeMode::Auto, ホレ / ラタ switch the table used for the following codes
+    codes are reported as ambiguous only if no marker is found
+*/
+pub fn decode(codes: &[MorseChar], mode: DecodeMode) -> Vec<Decoded> {
+    const START: MorseChar = MorseChar::Char(WABUN_START);
+    const END: MorseChar = MorseChar::Char(WABUN_END);
+
+    let auto = mode == DecodeMode::Auto;
+    // the first marker tells which table was used before it
+    let mut mode = match codes.iter().find(|&&c| auto && (c == START || c == END)) {
+        Some(&START) => DecodeMode::Table(CodeTable::International),
+        Some(_) => DecodeMode::Table(CodeTable::Wabun),
+        None => mode,
+    };
+    let mut v = Vec::<Decoded>::new();
+    for &c in codes {
+        let table = match c {
+            START if auto => CodeTable::Wabun,
+            END if auto => CodeTable::International,
+            _ => {
+                let d = decode_char(c, mode);
+                // the marker is sent as a word; do not double the space
+                if d == Decoded::Space
+                    && matches!(v.last(), Some(Decoded::Switch(_)))
+                    && (v.len() < 2 || v[v.len() - 2] == Decoded::Space)
+                {
+                    continue;
+                }
+                v.push(d);
+                continue;
+            }
+        };
+        mode = DecodeMode::Table(table);
+        v.push(Decoded::Switch(table));
+    }
+    v
+}
+
+// dakuten and handakuten are decoded as combining marks; compose them here
+pub fn decode_to_string(codes: &[MorseChar], mode: DecodeMode) -> String {
+    decode(codes, mode)
+        .iter()
+        .map(|d| d.to_string())
+        .collect::<String>()
+        .nfc()
+        .collect()
+}
+
+/*
+    decodes codes meant to be the labeled ones, each with the script of the label at the same position
+    kana are encoded from the wabun table whatever the table is, so they are read back with it
+    codes past the labels use the script of the last one
+*/
+pub fn decode_as(codes: &[MorseChar], labeled: &[(MorseChar, String)], table: CodeTable) -> String {
+    let mut t = table;
+    let mut s = String::new();
+    for (i, &c) in codes.iter().enumerate() {
+        let script = labeled
+            .get(i)
+            .and_then(|(_, l)| l.chars().next())
+            .and_then(get_script);
+        match script {
+            Some(true) => t = CodeTable::Wabun,
+            Some(false) => t = table,
+            None => {}
+        }
+        s.push_str(&decode_char(c, DecodeMode::Table(t)).to_string());
+    }
+    s.nfc().collect()
+}
+
+/*
+    parses dot/dash notation like ".- -... / -.-."
+    characters are separated by whitespace, words by '/'
+*/
+pub fn parse_morse_str(s: &str) -> anyhow::Result<Vec<MorseChar>> {
+    let mut v = Vec::<MorseChar>::new();
+    for (i, word) in s.split('/').enumerate() {
+        if i > 0 && v.last().map(|x| !x.is_space()).unwrap_or(false) {
+            v.push(MorseChar::Space);
+        }
+        for c in word.split_whitespace() {
+            v.push(MorseChar::Char(c.parse()?));
+        }
+    }
+    Ok(v)
+}
+
+pub fn dot_time(wpm: f32) -> std::time::Duration {
+    Timing::new(wpm).dot()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SP: MorseChar = MorseChar::Space;
+
+    fn c(len: u8, bits: u16) -> MorseChar {
+        MorseChar::Char(Code::new(len, bits))
+    }
+
+    fn p(len: u8, bits: u16) -> MorseChar {
+        MorseChar::Prosign(Code::new(len, bits))
+    }
+
+    #[test]
+    fn test_morse_normal() {
+        assert_eq!(
+            get_morse_str("ABC".to_string()),
+            [c(2, 0b01), c(4, 0b1000), c(4, 0b1010),]
+        );
+    }
+
+    #[test]
+    fn test_morse_whitespace() {
+        // whitespace in a row should be merged
+        assert_eq!(
+            get_morse_str("A A  A\n　A".to_string()),
+            [c(2, 0b01), SP, c(2, 0b01), SP, c(2, 0b01), SP, c(2, 0b01),]
+        );
+    }
+
+    #[test]
+    fn test_morse_kana() {
+        assert_eq!(
+            get_morse_str("イロハ".to_string()),
+            [c(2, 0b01), c(4, 0b0101), c(4, 0b1000),]
+        );
+    }
+
+    #[test]
+    fn test_morse_normalize() {
+        let opts = EncodeOptions {
+            wabun_markers: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_morse_str_with("がガパAＡaａ".to_string(), &opts),
+            [
+                c(4, 0b0100),
+                c(2, 0b00),
+                c(4, 0b0100),
+                c(2, 0b00),
+                c(4, 0b1000),
+                c(5, 0b00110),
+                c(2, 0b01),
+                c(2, 0b01),
+                c(2, 0b01),
+                c(2, 0b01),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_morse_wabun_markers() {
+        assert_eq!(
+            get_morse_str("A1イ-A".to_string()),
+            [
+                c(2, 0b01),
+                c(5, 0b01111),
+                SP,
+                MorseChar::Char(WABUN_START),
+                SP,
+                c(2, 0b01),
+                c(6, 0b100001),
+                SP,
+                MorseChar::Char(WABUN_END),
+                SP,
+                c(2, 0b01),
+            ]
+        );
+        // no marker at the beginning
+        assert_eq!(
+            get_morse_str("イ A".to_string()),
+            [c(2, 0b01), SP, MorseChar::Char(WABUN_END), SP, c(2, 0b01)]
+        );
+        let opts = EncodeOptions {
+            wabun_markers: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_morse_str_with("A イ".to_string(), &opts),
+            [c(2, 0b01), SP, c(2, 0b01)]
+        );
+    }
+
+    #[test]
+    fn test_morse_tables() {
+        // letters of other tables are not dropped even if the table is not selected
+        assert_eq!(
+            get_morse_str("Щи Σ Äé".to_string()),
+            [
+                c(4, 0b1101),
+                c(2, 0b00),
+                SP,
+                c(3, 0b000),
+                SP,
+                c(4, 0b0101),
+                c(5, 0b00100),
+            ]
+        );
+        // unknown accents are dropped
+        assert_eq!(get_morse_str("ЁÂ".to_string()), [c(1, 0b0), c(2, 0b01)]);
+        // 한 = ㅎ ㅏ ㄴ, 꽈 = ㄱ ㄱ ㅗ ㅏ
+        assert_eq!(
+            get_morse_str("한꽈".to_string()),
+            [
+                c(4, 0b0111),
+                c(1, 0b0),
+                c(4, 0b0010),
+                c(4, 0b0100),
+                c(4, 0b0100),
+                c(2, 0b01),
+                c(1, 0b0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_morse_digraph() {
+        let opts = EncodeOptions {
+            table: CodeTable::Latin,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_morse_str_with("Ach".to_string(), &opts),
+            [c(2, 0b01), c(4, 0b1111)]
+        );
+        assert_eq!(
+            get_morse_str("Ach".to_string()),
+            [c(2, 0b01), c(4, 0b1010), c(4, 0b0000)]
+        );
+        assert_eq!(
+            decode_to_string(
+                &get_morse_str_with("Bach, Öl".to_string(), &opts),
+                DecodeMode::Table(CodeTable::Latin)
+            ),
+            "BACH, ÖL"
+        );
+    }
+
+    #[test]
+    fn test_morse_labeled() {
+        let opts = EncodeOptions {
+            table: CodeTable::Latin,
+            wabun_markers: false,
+        };
+        let v = Encoder::new(&opts).encode_labeled("Ach ガ");
+        assert_eq!(
+            v,
+            [
+                (c(2, 0b01), "A".to_string()),
+                (c(4, 0b1111), "CH".to_string()),
+                (SP, " ".to_string()),
+                (c(4, 0b0100), "ガ".to_string()),
+                (c(2, 0b00), "ガ".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_morse_prosign() {
+        assert_eq!(
+            get_morse_str("K<AR> <sos><HH>".to_string()),
+            [
+                c(3, 0b101),
+                p(5, 0b01010),
+                SP,
+                p(9, 0b000111000),
+                p(8, 0b00000000),
+            ]
+        );
+        // not a prosign; brackets are ignored
+        assert_eq!(
+            get_morse_str("<A B>".to_string()),
+            [SP, c(2, 0b01), SP, c(4, 0b1000), SP]
+        );
+
+        let full = Code::new(16, 0xffff);
+        assert_eq!(Code::new(0, 0).concat(full), Some(full));
+        assert_eq!(full.concat(Code::new(0, 0)), Some(full));
+        assert_eq!(Code::new(1, 0).concat(full), None);
+    }
+
+    #[test]
+    fn test_decode() {
+        let codes = get_morse_str("CQ DE JA1ABC".to_string());
+        assert_eq!(
+            decode_to_string(&codes, DecodeMode::Table(CodeTable::International)),
+            "CQ DE JA1ABC"
+        );
+    }
+
+    #[test]
+    fn test_decode_wabun() {
+        let codes = get_morse_str("ガクセイ".to_string());
+        assert_eq!(
+            decode_to_string(&codes, DecodeMode::Table(CodeTable::Wabun)),
+            "ガクセイ"
+        );
+    }
+
+    #[test]
+    fn test_decode_table() {
+        let codes = get_morse_str("Привет 73".to_string());
+        assert_eq!(
+            decode_to_string(&codes, DecodeMode::Table(CodeTable::Russian)),
+            "ПРИВЕТ 73"
+        );
+        assert_eq!(
+            decode_to_string(&codes, DecodeMode::Table(CodeTable::International)),
+            "PRIWET 73"
+        );
+        assert_eq!("greek".parse::<CodeTable>().unwrap(), CodeTable::Greek);
+        assert!("klingon".parse::<CodeTable>().is_err());
+    }
+
+    #[test]
+    fn test_decode_switch() {
+        let codes = get_morse_str("JA1ABC デス <SK>".to_string());
+        assert_eq!(
+            decode_to_string(&codes, DecodeMode::Auto),
+            "JA1ABC デス <SK>"
+        );
+    }
+
+    #[test]
+    fn test_decode_ambiguous() {
+        assert_eq!(
+            decode(
+                &[c(2, 0b01), c(5, 0b11011), c(6, 0b001100)],
+                DecodeMode::Auto
+            ),
+            [
+                Decoded::Ambiguous {
+                    international: 'A',
+                    wabun: 'イ'
+                },
+                Decoded::Char('ア'),
+                Decoded::Char('?'),
+            ]
+        );
+        assert_eq!(
+            decode(&[c(8, 0b11111111)], DecodeMode::Auto),
+            [Decoded::Unknown(Code::new(8, 0b11111111))]
+        );
+        assert_eq!(
+            decode(
+                &[c(6, 0b000101), c(5, 0b01010)],
+                DecodeMode::Table(CodeTable::International)
+            ),
+            [Decoded::Prosign("SK"), Decoded::Char('+')]
+        );
+        assert_eq!(
+            decode_to_string(&get_morse_str("+<AR>".to_string()), DecodeMode::Auto),
+            "[+|ン]<AR>"
+        );
+    }
+
+    #[test]
+    fn test_elements() {
+        use Element::*;
+        let codes = get_morse_str("AN E".to_string());
+        assert_eq!(
+            elements(&codes).collect::<Vec<_>>(),
+            [Dot, ElementGap, Dash, CharGap, Dash, ElementGap, Dot, WordGap, Dot]
+        );
+        assert_eq!(to_notation(&codes), ".- -. / .");
+        assert_eq!(parse_morse_str(&to_notation(&codes)).unwrap(), codes);
+        assert_eq!("-.-.".parse::<Code>().unwrap(), Code::new(4, 0b1010));
+        assert_eq!(Code::new(4, 0b1010).to_string(), "-.-.");
+        assert_eq!(<(u8, u16)>::from(Code::new(2, 0b01)), (2, 0b01));
+    }
+
+    #[test]
+    fn test_parse_morse_str() {
+        assert_eq!(
+            parse_morse_str(".- -... / -.-.").unwrap(),
+            [c(2, 0b01), c(4, 0b1000), SP, c(4, 0b1010)]
+        );
+        assert_eq!(parse_morse_str(" .-  / ").unwrap(), [c(2, 0b01), SP]);
+        assert!(parse_morse_str(".-x").is_err());
+    }
+}
@@ -0,0 +1,413 @@
+use super::Code;
+
+/// code table to encode characters with, and to read codes back into characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CodeTable {
+    #[default]
+    International,
+    Wabun,
+    // accented latin letters like Ä, É, Ñ, and CH
+    Latin,
+    Russian,
+    Greek,
+    // SKATS
+    Korean,
+}
+
+impl CodeTable {
+    pub const ALL: [CodeTable; 6] = [
+        CodeTable::International,
+        CodeTable::Wabun,
+        CodeTable::Latin,
+        CodeTable::Russian,
+        CodeTable::Greek,
+        CodeTable::Korean,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CodeTable::International => "international",
+            CodeTable::Wabun => "wabun",
+            CodeTable::Latin => "latin",
+            CodeTable::Russian => "russian",
+            CodeTable::Greek => "greek",
+            CodeTable::Korean => "korean",
+        }
+    }
+
+    fn entries(&self) -> &'static [(char, u8, u16)] {
+        match self {
+            CodeTable::International => INTERNATIONAL,
+            CodeTable::Wabun => WABUN,
+            CodeTable::Latin => LATIN,
+            CodeTable::Russian => RUSSIAN,
+            CodeTable::Greek => GREEK,
+            CodeTable::Korean => KOREAN,
+        }
+    }
+
+    // sequences of characters keyed as one code
+    pub fn digraphs(&self) -> &'static [(&'static str, Code)] {
+        match self {
+            CodeTable::Latin => LATIN_DIGRAPHS,
+            _ => &[],
+        }
+    }
+
+    // code of a character in this table
+    pub fn get(&self, c: char) -> Option<Code> {
+        let c = to_upper(c);
+        self.entries()
+            .iter()
+            .find(|e| e.0 == c)
+            .map(|&(_, l, b)| Code::new(l, b))
+    }
+
+    // codes of a character keyed as a sequence of letters (Hangul syllables)
+    pub fn get_expanded(&self, c: char) -> Option<Vec<Code>> {
+        match self {
+            CodeTable::Korean => decompose_hangul(c)?
+                .into_iter()
+                .map(|c| self.get(c))
+                .collect(),
+            _ => None,
+        }
+    }
+
+    pub fn lookup(&self, code: Code) -> Option<char> {
+        self.entries()
+            .iter()
+            .find(|&&(_, l, b)| Code::new(l, b) == code)
+            .map(|e| e.0)
+            .or_else(|| match self {
+                CodeTable::International => None,
+                // digits are shared with the international table
+                CodeTable::Wabun => CodeTable::International
+                    .lookup(code)
+                    .filter(|c| c.is_ascii_digit()),
+                _ => CodeTable::International.lookup(code),
+            })
+    }
+
+    pub fn lookup_digraph(&self, code: Code) -> Option<&'static str> {
+        self.digraphs()
+            .iter()
+            .find(|(_, m)| *m == code)
+            .map(|(s, _)| *s)
+    }
+}
+
+crate::util::impl_named!(CodeTable, "code table");
+
+fn to_upper(c: char) -> char {
+    let mut u = c.to_uppercase();
+    match (u.next(), u.next()) {
+        (Some(u), None) => u,
+        _ => c, // e.g. ß
+    }
+}
+
+const HANGUL_INITIALS: &str = "ㄱㄲㄴㄷㄸㄹㅁㅂㅃㅅㅆㅇㅈㅉㅊㅋㅌㅍㅎ";
+const HANGUL_MEDIALS: &str = "ㅏㅐㅑㅒㅓㅔㅕㅖㅗㅘㅙㅚㅛㅜㅝㅞㅟㅠㅡㅢㅣ";
+const HANGUL_FINALS: &str = "ㄱㄲㄳㄴㄵㄶㄷㄹㄺㄻㄼㄽㄾㄿㅀㅁㅂㅄㅅㅆㅇㅈㅊㅋㅌㅍㅎ";
+
+// splits a syllable or a compound letter into basic letters
+fn decompose_hangul(c: char) -> Option<Vec<char>> {
+    let mut v = Vec::new();
+    if ('가'..='힣').contains(&c) {
+        let i = c as usize - '가' as usize;
+        v.push(HANGUL_INITIALS.chars().nth(i / 588)?);
+        v.push(HANGUL_MEDIALS.chars().nth(i % 588 / 28)?);
+        if !i.is_multiple_of(28) {
+            v.push(HANGUL_FINALS.chars().nth(i % 28 - 1)?);
+        }
+    } else {
+        v.push(c);
+    }
+
+    let v = v
+        .into_iter()
+        .flat_map(|c| {
+            let s = match c {
+                'ㄲ' => "ㄱㄱ",
+                'ㄸ' => "ㄷㄷ",
+                'ㅃ' => "ㅂㅂ",
+                'ㅆ' => "ㅅㅅ",
+                'ㅉ' => "ㅈㅈ",
+                'ㄳ' => "ㄱㅅ",
+                'ㄵ' => "ㄴㅈ",
+                'ㄶ' => "ㄴㅎ",
+                'ㄺ' => "ㄹㄱ",
+                'ㄻ' => "ㄹㅁ",
+                'ㄼ' => "ㄹㅂ",
+                'ㄽ' => "ㄹㅅ",
+                'ㄾ' => "ㄹㅌ",
+                'ㄿ' => "ㄹㅍ",
+                'ㅀ' => "ㄹㅎ",
+                'ㅄ' => "ㅂㅅ",
+                'ㅒ' => "ㅑㅣ",
+                'ㅖ' => "ㅕㅣ",
+                'ㅘ' => "ㅗㅏ",
+                'ㅙ' => "ㅗㅐ",
+                'ㅚ' => "ㅗㅣ",
+                'ㅝ' => "ㅜㅓ",
+                'ㅞ' => "ㅜㅔ",
+                'ㅟ' => "ㅜㅣ",
+                'ㅢ' => "ㅡㅣ",
+                _ => return vec![c],
+            };
+            s.chars().collect()
+        })
+        .collect::<Vec<_>>();
+
+    if v.len() == 1 && v[0] == c {
+        None // nothing to expand
+    } else {
+        Some(v)
+    }
+}
+
+/*
+    (character, length, binary expression)
+    0 = dot, 1 = dash; MSB first
+*/
+const INTERNATIONAL: &[(char, u8, u16)] = &[
+    ('0', 5, 0b11111),
+    ('1', 5, 0b01111),
+    ('2', 5, 0b00111),
+    ('3', 5, 0b00011),
+    ('4', 5, 0b00001),
+    ('5', 5, 0b00000),
+    ('6', 5, 0b10000),
+    ('7', 5, 0b11000),
+    ('8', 5, 0b11100),
+    ('9', 5, 0b11110),
+    ('A', 2, 0b01),
+    ('B', 4, 0b1000),
+    ('C', 4, 0b1010),
+    ('D', 3, 0b100),
+    ('E', 1, 0b0),
+    ('F', 4, 0b0010),
+    ('G', 3, 0b110),
+    ('H', 4, 0b0000),
+    ('I', 2, 0b00),
+    ('J', 4, 0b0111),
+    ('K', 3, 0b101),
+    ('L', 4, 0b0100),
+    ('M', 2, 0b11),
+    ('N', 2, 0b10),
+    ('O', 3, 0b111),
+    ('P', 4, 0b0110),
+    ('Q', 4, 0b1101),
+    ('R', 3, 0b010),
+    ('S', 3, 0b000),
+    ('T', 1, 0b1),
+    ('U', 3, 0b001),
+    ('V', 4, 0b0001),
+    ('W', 3, 0b011),
+    ('X', 4, 0b1001),
+    ('Y', 4, 0b1011),
+    ('Z', 4, 0b1100),
+    ('!', 6, 0b101011),
+    ('"', 6, 0b010010),
+    ('$', 7, 0b0001001),
+    ('&', 5, 0b01000),
+    ('(', 5, 0b10110),
+    (')', 6, 0b101101),
+    ('+', 5, 0b01010),
+    (',', 6, 0b110011),
+    ('-', 6, 0b100001),
+    ('.', 6, 0b010101),
+    ('/', 5, 0b10010),
+    (':', 6, 0b111000),
+    (';', 6, 0b101010),
+    ('=', 5, 0b10001),
+    ('?', 6, 0b001100),
+    ('@', 6, 0b011010),
+    ('\'', 6, 0b011110),
+    ('_', 6, 0b001101),
+];
+
+const WABUN: &[(char, u8, u16)] = &[
+    ('ア', 5, 0b11011),
+    ('イ', 2, 0b01),
+    ('ウ', 3, 0b001),
+    ('エ', 5, 0b10111),
+    ('オ', 5, 0b01000),
+    ('カ', 4, 0b0100),
+    ('キ', 5, 0b10100),
+    ('ク', 4, 0b0001),
+    ('ケ', 4, 0b1011),
+    ('コ', 4, 0b1111),
+    ('サ', 5, 0b10101),
+    ('シ', 5, 0b11010),
+    ('ス', 5, 0b11101),
+    ('セ', 5, 0b01110),
+    ('ソ', 4, 0b1110),
+    ('タ', 2, 0b10),
+    ('チ', 4, 0b0010),
+    ('ツ', 4, 0b0110),
+    ('テ', 5, 0b01011),
+    ('ト', 5, 0b00100),
+    ('ナ', 3, 0b010),
+    ('ニ', 4, 0b1010),
+    ('ヌ', 4, 0b0000),
+    ('ネ', 4, 0b1101),
+    ('ノ', 4, 0b0011),
+    ('ハ', 4, 0b1000),
+    ('ヒ', 5, 0b11001),
+    ('フ', 4, 0b1100),
+    ('ヘ', 1, 0b0),
+    ('ホ', 3, 0b100),
+    ('マ', 4, 0b1001),
+    ('ミ', 5, 0b00101),
+    ('ム', 1, 0b1),
+    ('メ', 5, 0b10001),
+    ('モ', 5, 0b10010),
+    ('ヤ', 3, 0b011),
+    ('ユ', 5, 0b10011),
+    ('ヨ', 2, 0b11),
+    ('ラ', 3, 0b000),
+    ('リ', 3, 0b110),
+    ('ル', 5, 0b10110),
+    ('レ', 3, 0b111),
+    ('ロ', 4, 0b0101),
+    ('ワ', 3, 0b101),
+    ('ヰ', 5, 0b01001),
+    ('ヱ', 5, 0b01100),
+    ('ヲ', 4, 0b0111),
+    ('ン', 5, 0b01010),
+    ('\u{3099}', 2, 0b00),    // COMBINING dakuten
+    ('\u{309A}', 5, 0b00110), // COMBINING handakuten
+    ('゛', 2, 0b00),
+    ('゜', 5, 0b00110),
+    ('ー', 5, 0b01101),
+    ('、', 6, 0b010101),
+];
+
+// on decoding, the first one is used for codes shared by some letters
+const LATIN: &[(char, u8, u16)] = &[
+    ('À', 5, 0b01101),
+    ('Å', 5, 0b01101),
+    ('Ä', 4, 0b0101),
+    ('Ą', 4, 0b0101),
+    ('Æ', 4, 0b0101),
+    ('Ç', 5, 0b10100),
+    ('Ć', 5, 0b10100),
+    ('Ĉ', 5, 0b10100),
+    ('È', 5, 0b01001),
+    ('Ł', 5, 0b01001),
+    ('É', 5, 0b00100),
+    ('Ę', 5, 0b00100),
+    ('Ð', 5, 0b00110),
+    ('Ĝ', 5, 0b11010),
+    ('Ĥ', 4, 0b1111),
+    ('Š', 4, 0b1111),
+    ('Ĵ', 5, 0b01110),
+    ('Ñ', 5, 0b11011),
+    ('Ń', 5, 0b11011),
+    ('Ö', 4, 0b1110),
+    ('Ó', 4, 0b1110),
+    ('Ø', 4, 0b1110),
+    ('Ś', 7, 0b0001000),
+    ('Ŝ', 5, 0b00010),
+    ('Þ', 5, 0b01100),
+    ('Ü', 4, 0b0011),
+    ('Ŭ', 4, 0b0011),
+    ('Ź', 6, 0b110010),
+    ('Ż', 5, 0b11001),
+    ('ß', 7, 0b0001100),
+];
+
+const LATIN_DIGRAPHS: &[(&str, Code)] = &[("CH", Code::new(4, 0b1111))];
+
+const RUSSIAN: &[(char, u8, u16)] = &[
+    ('А', 2, 0b01),
+    ('Б', 4, 0b1000),
+    ('В', 3, 0b011),
+    ('Г', 3, 0b110),
+    ('Д', 3, 0b100),
+    ('Е', 1, 0b0),
+    ('Ж', 4, 0b0001),
+    ('З', 4, 0b1100),
+    ('И', 2, 0b00),
+    ('Й', 4, 0b0111),
+    ('К', 3, 0b101),
+    ('Л', 4, 0b0100),
+    ('М', 2, 0b11),
+    ('Н', 2, 0b10),
+    ('О', 3, 0b111),
+    ('П', 4, 0b0110),
+    ('Р', 3, 0b010),
+    ('С', 3, 0b000),
+    ('Т', 1, 0b1),
+    ('У', 3, 0b001),
+    ('Ф', 4, 0b0010),
+    ('Х', 4, 0b0000),
+    ('Ц', 4, 0b1010),
+    ('Ч', 4, 0b1110),
+    ('Ш', 4, 0b1111),
+    ('Щ', 4, 0b1101),
+    ('Ъ', 5, 0b11011),
+    ('Ы', 4, 0b1011),
+    ('Ь', 4, 0b1001),
+    ('Э', 5, 0b00100),
+    ('Ю', 4, 0b0011),
+    ('Я', 4, 0b0101),
+];
+
+const GREEK: &[(char, u8, u16)] = &[
+    ('Α', 2, 0b01),
+    ('Β', 4, 0b1000),
+    ('Γ', 3, 0b110),
+    ('Δ', 3, 0b100),
+    ('Ε', 1, 0b0),
+    ('Ζ', 4, 0b1100),
+    ('Η', 4, 0b0000),
+    ('Θ', 4, 0b1010),
+    ('Ι', 2, 0b00),
+    ('Κ', 3, 0b101),
+    ('Λ', 4, 0b0100),
+    ('Μ', 2, 0b11),
+    ('Ν', 2, 0b10),
+    ('Ξ', 4, 0b1001),
+    ('Ο', 3, 0b111),
+    ('Π', 4, 0b0110),
+    ('Ρ', 3, 0b010),
+    ('Σ', 3, 0b000),
+    ('Τ', 1, 0b1),
+    ('Υ', 4, 0b1011),
+    ('Φ', 4, 0b0010),
+    ('Χ', 4, 0b1111),
+    ('Ψ', 4, 0b1101),
+    ('Ω', 3, 0b011),
+];
+
+const KOREAN: &[(char, u8, u16)] = &[
+    ('ㄱ', 4, 0b0100),
+    ('ㄴ', 4, 0b0010),
+    ('ㄷ', 4, 0b1000),
+    ('ㄹ', 4, 0b0001),
+    ('ㅁ', 2, 0b11),
+    ('ㅂ', 3, 0b011),
+    ('ㅅ', 3, 0b110),
+    ('ㅇ', 3, 0b101),
+    ('ㅈ', 4, 0b0110),
+    ('ㅊ', 4, 0b1010),
+    ('ㅋ', 4, 0b1001),
+    ('ㅌ', 4, 0b1100),
+    ('ㅍ', 3, 0b111),
+    ('ㅎ', 4, 0b0111),
+    ('ㅏ', 1, 0b0),
+    ('ㅑ', 2, 0b00),
+    ('ㅓ', 1, 0b1),
+    ('ㅕ', 3, 0b000),
+    ('ㅗ', 2, 0b01),
+    ('ㅛ', 2, 0b10),
+    ('ㅜ', 4, 0b0000),
+    ('ㅠ', 3, 0b010),
+    ('ㅡ', 3, 0b100),
+    ('ㅣ', 3, 0b001),
+    ('ㅐ', 4, 0b1101),
+    ('ㅔ', 4, 0b1011),
+];
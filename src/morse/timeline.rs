@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use super::fist::{Fist, Hand};
+use super::{Element, MorseChar, Timing};
+
+/// exact ON/OFF sequence to be keyed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    events: Vec<(f64, bool)>, // (length in seconds, ON/OFF)
+    char_starts: Vec<usize>,  // index of the first event of each code (MorseChar)
+}
+
+impl Timeline {
+    pub fn new(codes: &[MorseChar], timing: &Timing) -> Self {
+        Self::with_fist(codes, timing, &Fist::default())
+    }
+
+    // keyed by a human sender
+    pub fn with_fist(codes: &[MorseChar], timing: &Timing, fist: &Fist) -> Self {
+        let mut keyer = Keyer::new(timing, fist);
+        let mut events = keyer.lead();
+        let mut char_starts = Vec::new();
+
+        for &c in codes {
+            let gap = keyer.gap_before(c);
+            events.extend(gap);
+            char_starts.push(events.len());
+            events.extend(keyer.key(c));
+        }
+        events.extend(keyer.finish());
+
+        Self {
+            events,
+            char_starts,
+        }
+    }
+
+    pub fn events(&self) -> &[(f64, bool)] {
+        &self.events
+    }
+
+    pub fn char_starts(&self) -> &[usize] {
+        &self.char_starts
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.events.iter().map(|e| e.0).sum())
+    }
+
+    /*
+        (length in samples, ON/OFF)
+        event boundaries are rounded on the absolute position so that errors do not accumulate
+    */
+    pub fn to_samples(&self, srate: usize) -> Vec<(usize, bool)> {
+        let mut t = 0.0;
+        let mut pos = 0;
+        self.events
+            .iter()
+            .map(|&(l, on)| {
+                t += l;
+                let end = (t * srate as f64).round() as usize;
+                let len = end - pos;
+                pos = end;
+                (len, on)
+            })
+            .collect()
+    }
+
+    // length in samples
+    pub fn len_samples(&self, srate: usize) -> usize {
+        (self.events.iter().map(|e| e.0).sum::<f64>() * srate as f64).round() as usize
+    }
+}
+
+/// turns characters into ON/OFF events one by one, for text not known in advance
+pub struct Keyer {
+    timing: Timing,
+    dot: f32,
+    hand: Hand,
+    last: Option<MorseChar>,
+}
+
+impl Keyer {
+    pub fn new(timing: &Timing, fist: &Fist) -> Self {
+        let timing = fist.timing(timing);
+        Self {
+            dot: timing.dot().as_secs_f32(),
+            timing,
+            hand: Hand::new(fist),
+            last: None,
+        }
+    }
+
+    fn event(&mut self, e: Element) -> (f64, bool) {
+        let secs = self
+            .hand
+            .element_secs(e, self.timing.element_secs(e), self.dot);
+        (secs as f64, e.is_mark())
+    }
+
+    // pause before the first character
+    pub fn lead(&self) -> Vec<(f64, bool)> {
+        vec![(self.dot as f64 * 2.0, false)]
+    }
+
+    // same gaps as elements()
+    pub fn gap_before(&mut self, c: MorseChar) -> Option<(f64, bool)> {
+        match self.last {
+            Some(l) if !l.is_space() && !c.is_space() => Some(self.event(Element::CharGap)),
+            _ => None,
+        }
+    }
+
+    // events of c itself; call gap_before first
+    pub fn key(&mut self, c: MorseChar) -> Vec<(f64, bool)> {
+        self.last = Some(c);
+        c.elements().map(|e| self.event(e)).collect()
+    }
+
+    pub fn last(&self) -> Option<MorseChar> {
+        self.last
+    }
+
+    // gap after the last character
+    pub fn finish(&mut self) -> Option<(f64, bool)> {
+        Some(self.event(Element::CharGap))
+    }
+}
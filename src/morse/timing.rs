@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use super::Element;
+
+/// word used to define "words per minute"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReferenceWord {
+    #[default]
+    Paris,
+    Codex,
+}
+
+impl ReferenceWord {
+    pub const ALL: [ReferenceWord; 2] = [ReferenceWord::Paris, ReferenceWord::Codex];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReferenceWord::Paris => "paris",
+            ReferenceWord::Codex => "codex",
+        }
+    }
+
+    // (units in characters, units in gaps between them); both include 19 units of spacing
+    fn units(&self) -> (f32, f32) {
+        match self {
+            ReferenceWord::Paris => (31.0, 19.0),
+            ReferenceWord::Codex => (41.0, 19.0),
+        }
+    }
+}
+
+crate::util::impl_named!(ReferenceWord, "reference word");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timing {
+    // speed of characters themselves
+    pub char_wpm: f32,
+    // overall speed; gaps between characters and words are stretched (Farnsworth)
+    pub effective_wpm: Option<f32>,
+    // dash length in dots
+    pub dash_ratio: f32,
+    // 0.5 is standard; larger value lengthens marks and shortens gaps by the same amount
+    pub weight: f32,
+    // added to gaps, in dots
+    pub extra_char_space: f32,
+    pub extra_word_space: f32,
+    pub reference: ReferenceWord,
+}
+
+impl Timing {
+    pub fn new(wpm: f32) -> Self {
+        Self {
+            char_wpm: wpm,
+            effective_wpm: None,
+            dash_ratio: 3.0,
+            weight: 0.5,
+            extra_char_space: 0.0,
+            extra_word_space: 0.0,
+            reference: ReferenceWord::Paris,
+        }
+    }
+
+    pub fn farnsworth(char_wpm: f32, effective_wpm: f32) -> Self {
+        Self {
+            effective_wpm: Some(effective_wpm),
+            ..Self::new(char_wpm)
+        }
+    }
+
+    // length of a dot in seconds
+    fn unit(&self) -> f32 {
+        let (c, s) = self.reference.units();
+        60.0 / ((c + s) * self.char_wpm)
+    }
+
+    // length of a spacing unit between characters and words in seconds
+    // effective speed not positive is ignored, as if not given
+    fn space_unit(&self) -> f32 {
+        let u = self.unit();
+        match self.effective_wpm {
+            Some(e) if e > 0.0 && e < self.char_wpm => {
+                let (c, s) = self.reference.units();
+                (60.0 / e - c * u) / s
+            }
+            _ => u,
+        }
+    }
+
+    pub fn dot(&self) -> Duration {
+        Duration::from_secs_f32(self.unit())
+    }
+
+    // length of an element in seconds
+    pub fn element_secs(&self, e: Element) -> f32 {
+        let u = self.unit();
+        let w = (self.weight / 0.5 - 1.0) * u;
+        let t = match e {
+            Element::Dot => u + w,
+            Element::Dash => self.dash_ratio * u + w,
+            Element::ElementGap => u - w,
+            Element::CharGap => 3.0 * self.space_unit() + self.extra_char_space * u - w,
+            Element::WordGap => 7.0 * self.space_unit() + self.extra_word_space * u - w,
+        };
+        t.max(0.0)
+    }
+
+    pub fn element(&self, e: Element) -> Duration {
+        Duration::from_secs_f32(self.element_secs(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::morse::{elements, get_morse_str};
+
+    fn word_secs(t: &Timing, s: &str) -> f32 {
+        elements(&get_morse_str(s.to_string()))
+            .chain([Element::WordGap])
+            .map(|e| t.element_secs(e))
+            .sum()
+    }
+
+    #[test]
+    fn test_timing_standard() {
+        let t = Timing::new(20.0);
+        assert!((t.dot().as_secs_f32() - 0.06).abs() < 1e-6);
+        assert!((word_secs(&t, "PARIS") - 3.0).abs() < 1e-4);
+        assert!((t.element_secs(Element::Dash) - 0.18).abs() < 1e-6);
+
+        let t = Timing {
+            reference: ReferenceWord::Codex,
+            ..Timing::new(20.0)
+        };
+        assert!((word_secs(&t, "CODEX") - 3.0).abs() < 1e-4);
+
+        for r in ReferenceWord::ALL {
+            assert_eq!(r.name().parse::<ReferenceWord>().unwrap(), r);
+        }
+        assert!("words".parse::<ReferenceWord>().is_err());
+    }
+
+    #[test]
+    fn test_timing_farnsworth() {
+        let t = Timing::farnsworth(20.0, 10.0);
+        // characters are keyed at 20 wpm, words are sent at 10 wpm
+        assert!((t.element_secs(Element::Dot) - 0.06).abs() < 1e-6);
+        assert!((word_secs(&t, "PARIS") - 6.0).abs() < 1e-4);
+
+        // effective speed faster than char speed is ignored
+        let t = Timing::farnsworth(20.0, 30.0);
+        assert!((word_secs(&t, "PARIS") - 3.0).abs() < 1e-4);
+
+        // and so is one not positive
+        for e in [0.0, -5.0] {
+            let t = Timing::farnsworth(20.0, e);
+            assert!((word_secs(&t, "PARIS") - 3.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_timing_weight() {
+        let t = Timing {
+            weight: 0.6,
+            dash_ratio: 3.5,
+            ..Timing::new(20.0)
+        };
+        let dot = t.element_secs(Element::Dot);
+        let gap = t.element_secs(Element::ElementGap);
+        assert!((dot - 0.072).abs() < 1e-6);
+        assert!((dot + gap - 0.12).abs() < 1e-6);
+        assert!((t.element_secs(Element::Dash) - 0.222).abs() < 1e-6);
+    }
+}
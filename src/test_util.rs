@@ -0,0 +1,57 @@
+// helpers shared by the tests
+
+// reads until the end, with read returning the number of samples written
+pub fn read_all(mut read: impl FnMut(&mut [f32]) -> usize) -> Vec<f32> {
+    let mut v = Vec::new();
+    let mut buf = vec![0.0; 1000];
+    loop {
+        let n = read(&mut buf);
+        if n == 0 {
+            break;
+        }
+        v.extend_from_slice(&buf[..n]);
+    }
+    v
+}
+
+// reads f32 samples in native byte order until the end, as songbird does
+pub fn read_all_bytes(r: &mut impl std::io::Read) -> Vec<f32> {
+    let mut v = Vec::new();
+    let mut buf = vec![0u8; 4096];
+    loop {
+        let n = r.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        v.extend(
+            buf[..n]
+                .chunks(4)
+                .map(|b| f32::from_ne_bytes(b.try_into().unwrap())),
+        );
+    }
+    v
+}
+
+// power of the component at freq (Goertzel), normalized as a sine power
+pub fn tone_power(v: &[f32], freq: f32, srate: usize) -> f64 {
+    let k = 2.0 * (2.0 * std::f64::consts::PI * freq as f64 / srate as f64).cos();
+    let (mut s1, mut s2) = (0.0, 0.0);
+    for &x in v {
+        let s0 = x as f64 + k * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    2.0 * (s1 * s1 + s2 * s2 - k * s1 * s2) / (v.len() as f64).powi(2)
+}
+
+// 48kHz mono as discord sends it: timestamped 20ms packets in stereo, none while silent
+pub fn packets(samples: &[f32]) -> impl Iterator<Item = (u32, Vec<i16>)> + '_ {
+    samples
+        .chunks(960)
+        .enumerate()
+        .filter(|(_, p)| p.iter().any(|&x| x != 0.0))
+        .map(|(i, p)| {
+            let audio = p.iter().flat_map(|&x| [(x * 16384.0) as i16; 2]).collect();
+            (i as u32 * 960, audio)
+        })
+}
@@ -0,0 +1,62 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// the same seed gives the same stream; None for a random one
+pub fn rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(s) => StdRng::seed_from_u64(s),
+        None => StdRng::from_entropy(),
+    }
+}
+
+// gaussian, variance 1 (Box–Muller)
+pub(crate) fn gauss(rng: &mut impl Rng) -> f32 {
+    let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let u2: f32 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
+}
+
+// insertions, deletions and substitutions to turn a into b
+pub(crate) fn edit_distance<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            cur.push(
+                (prev[j] + (ca != cb) as usize)
+                    .min(prev[j + 1] + 1)
+                    .min(cur[j] + 1),
+            );
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+// FromStr and Display by name(), for enums listing their variants in ALL
+macro_rules! impl_named {
+    ($t:ident, $what:literal) => {
+        impl std::str::FromStr for $t {
+            type Err = anyhow::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                use anyhow::Context as _;
+                $t::ALL
+                    .into_iter()
+                    .find(|t| t.name() == s)
+                    .with_context(|| {
+                        format!(
+                            concat!("unknown ", $what, ". available selections are: {}"),
+                            $t::ALL.map(|t| t.name()).join(", ")
+                        )
+                    })
+            }
+        }
+
+        impl std::fmt::Display for $t {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.name())
+            }
+        }
+    };
+}
+pub(crate) use impl_named;